@@ -0,0 +1,171 @@
+#[cfg(test)]
+use std::convert::TryInto;
+use std::fs::File;
+#[cfg(test)]
+use std::io::{BufReader, Read};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[cfg(test)]
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+#[cfg(test)]
+use nalgebra::{Point2, Vector2};
+
+use crate::core::BodyView;
+
+/// id, sun flag, then position, velocity, mass and radius as little endian f64s
+const RECORD_SIZE: usize = 4 + 1 + 6 * 8;
+
+/// every body as it was at the end of a tick
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ArchivedTick {
+    pub(crate) tick: u64,
+    pub(crate) bodies: Vec<BodyView>,
+}
+
+/// Gzipped stream of per tick body states, each tick is its number and body count followed by
+/// one fixed width record per body, a lot smaller than the same run as text
+pub(crate) struct TrajectoryArchive {
+    encoder: GzEncoder<BufWriter<File>>,
+}
+
+impl TrajectoryArchive {
+    pub(crate) fn create(path: &Path) -> Result<TrajectoryArchive, String> {
+        let file = File::create(path)
+            .map_err(|error| format!("couldn't create {}: {}", path.display(), error))?;
+        Ok(TrajectoryArchive {
+            encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+        })
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, bodies: &[BodyView]) -> Result<(), String> {
+        let mut bytes = Vec::with_capacity(8 + 4 + bodies.len() * RECORD_SIZE);
+        bytes.extend_from_slice(&tick.to_le_bytes());
+        bytes.extend_from_slice(&(bodies.len() as u32).to_le_bytes());
+        for body in bodies {
+            bytes.extend_from_slice(&body.id.to_le_bytes());
+            bytes.push(body.sun as u8);
+            for value in &[
+                body.position.x,
+                body.position.y,
+                body.velocity.x,
+                body.velocity.y,
+                body.mass,
+                body.radius,
+            ] {
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        self.encoder
+            .write_all(&bytes)
+            .map_err(|error| format!("couldn't write trajectory archive: {}", error))
+    }
+
+    /// writes out the end of the gzip stream, dropping the archive does the same but can't report failure
+    pub(crate) fn finish(self) -> Result<(), String> {
+        self.encoder
+            .finish()
+            .and_then(|mut writer| writer.flush())
+            .map_err(|error| format!("couldn't finish trajectory archive: {}", error))
+    }
+}
+
+/// every tick in an archive written by `TrajectoryArchive`, in the order they were recorded,
+/// the app itself only records so this is only there to check archives in tests
+#[cfg(test)]
+pub(crate) fn read_archive(path: &Path) -> Result<Vec<ArchivedTick>, String> {
+    let file =
+        File::open(path).map_err(|error| format!("couldn't open {}: {}", path.display(), error))?;
+    let mut bytes = vec![];
+    GzDecoder::new(BufReader::new(file))
+        .read_to_end(&mut bytes)
+        .map_err(|error| format!("couldn't read {}: {}", path.display(), error))?;
+
+    let truncated = || format!("{} ends in the middle of a tick", path.display());
+    let mut ticks = vec![];
+    let mut rest = bytes.as_slice();
+    while !rest.is_empty() {
+        let header = rest.get(..12).ok_or_else(truncated)?;
+        let tick = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let count = u32::from_le_bytes(header[8..].try_into().unwrap()) as usize;
+        let records = rest
+            .get(12..12 + count * RECORD_SIZE)
+            .ok_or_else(truncated)?;
+        let bodies = records.chunks_exact(RECORD_SIZE).map(read_body).collect();
+        ticks.push(ArchivedTick { tick, bodies });
+        rest = &rest[12 + count * RECORD_SIZE..];
+    }
+    Ok(ticks)
+}
+
+#[cfg(test)]
+fn read_body(record: &[u8]) -> BodyView {
+    let value = |index: usize| {
+        let start = 5 + index * 8;
+        f64::from_le_bytes(record[start..start + 8].try_into().unwrap())
+    };
+    BodyView {
+        id: i32::from_le_bytes(record[..4].try_into().unwrap()),
+        sun: record[4] != 0,
+        position: Point2::new(value(0), value(1)),
+        velocity: Vector2::new(value(2), value(3)),
+        mass: value(4),
+        radius: value(5),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn archives_read_back_exactly_what_was_recorded() {
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-archive-{}.bin.gz", std::process::id()));
+        let sun = BodyView {
+            id: -1,
+            position: Point2::new(400., 300.),
+            velocity: Vector2::zeros(),
+            mass: 1000.,
+            radius: 10.,
+            sun: true,
+        };
+        let body = BodyView {
+            id: 7,
+            position: Point2::new(0.1 + 0.2, -1e-300),
+            velocity: Vector2::new(f64::MAX, -0.),
+            mass: 3.5,
+            radius: 1. / 3.,
+            sun: false,
+        };
+        let recorded = vec![
+            ArchivedTick {
+                tick: 1,
+                bodies: vec![sun, body],
+            },
+            ArchivedTick {
+                tick: 2,
+                bodies: vec![],
+            },
+            ArchivedTick {
+                tick: 3,
+                bodies: vec![sun],
+            },
+        ];
+
+        let mut archive = TrajectoryArchive::create(&path).unwrap();
+        for tick in &recorded {
+            archive.record(tick.tick, &tick.bodies).unwrap();
+        }
+        archive.finish().unwrap();
+
+        let read = read_archive(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), recorded);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use nalgebra::Point2;
+
+/// Uniform grid that buckets bodies by the cells their bounding box touches,
+/// only bodies sharing a cell can possibly collide
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SpatialGrid {
+    cell_size: f64,
+    cells: HashMap<(i32, i32), Vec<i32>>,
+}
+
+impl SpatialGrid {
+    pub(crate) fn new(cell_size: f64) -> SpatialGrid {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub(crate) fn insert(&mut self, id: i32, position: Point2<f64>, radius: f64) {
+        let (min_x, min_y) = self.cell_of(position.x - radius, position.y - radius);
+        let (max_x, max_y) = self.cell_of(position.x + radius, position.y + radius);
+        for cell in (min_x..=max_x).cartesian_product(min_y..=max_y) {
+            self.cells.entry(cell).or_default().push(id);
+        }
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// sorted ids of everything in the cells overlapping the square `range` around `position`,
+    /// callers still have to check the actual distance
+    pub(crate) fn nearby(&self, position: Point2<f64>, range: f64) -> Vec<i32> {
+        let (min_x, min_y) = self.cell_of(position.x - range, position.y - range);
+        let (max_x, max_y) = self.cell_of(position.x + range, position.y + range);
+        (min_x..=max_x)
+            .cartesian_product(min_y..=max_y)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// number of bodies touching each occupied cell
+    pub(crate) fn occupancy(&self) -> HashMap<(i32, i32), usize> {
+        self.cells
+            .iter()
+            .map(|(cell, ids)| (*cell, ids.len()))
+            .collect()
+    }
+
+    /// for every body, the sorted ids of the bodies it shares at least one cell with
+    pub(crate) fn neighbours(&self) -> HashMap<i32, Vec<i32>> {
+        let mut neighbours: HashMap<i32, Vec<i32>> = HashMap::new();
+        for ids in self.cells.values() {
+            for (left, right) in ids.iter().tuple_combinations() {
+                neighbours.entry(*left).or_default().push(*right);
+                neighbours.entry(*right).or_default().push(*left);
+            }
+        }
+        for ids in neighbours.values_mut() {
+            ids.sort_unstable();
+            ids.dedup();
+        }
+        neighbours
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_bodies_per_cell() {
+        let mut grid = SpatialGrid::new(10.);
+        grid.insert(0, Point2::new(2., 2.), 1.);
+        grid.insert(1, Point2::new(5., 5.), 1.);
+        grid.insert(2, Point2::new(15., 5.), 1.);
+        // straddles the border between (0, 1) and (1, 1)
+        grid.insert(3, Point2::new(10., 15.), 1.);
+
+        let occupancy = grid.occupancy();
+        assert_eq!(occupancy.len(), 4);
+        assert_eq!(occupancy[&(0, 0)], 2);
+        assert_eq!(occupancy[&(1, 0)], 1);
+        assert_eq!(occupancy[&(0, 1)], 1);
+        assert_eq!(occupancy[&(1, 1)], 1);
+    }
+
+    #[test]
+    fn bodies_in_shared_cells_are_neighbours() {
+        let mut grid = SpatialGrid::new(10.);
+        grid.insert(0, Point2::new(2., 2.), 1.);
+        grid.insert(1, Point2::new(5., 5.), 1.);
+        grid.insert(2, Point2::new(55., 55.), 1.);
+
+        let neighbours = grid.neighbours();
+        assert_eq!(neighbours[&0], vec![1]);
+        assert_eq!(neighbours[&1], vec![0]);
+        assert!(!neighbours.contains_key(&2));
+    }
+
+    #[test]
+    fn nearby_covers_the_cells_in_range() {
+        let mut grid = SpatialGrid::new(10.);
+        grid.insert(0, Point2::new(5., 5.), 0.);
+        grid.insert(1, Point2::new(15., 5.), 0.);
+        grid.insert(2, Point2::new(-5., -5.), 0.);
+        grid.insert(3, Point2::new(35., 5.), 0.);
+
+        assert_eq!(grid.nearby(Point2::new(5., 5.), 10.), vec![0, 1, 2]);
+        assert_eq!(grid.nearby(Point2::new(5., 5.), 1.), vec![0]);
+    }
+}
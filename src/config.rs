@@ -0,0 +1,1469 @@
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::fmt::Display;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use itertools::Itertools;
+
+use crate::{GRAVITATIONAL_CONSTANT, INITIAL_SPEED, NUM_BODIES};
+
+/// Tunables for a simulation run, the defaults reproduce the original behaviour
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Config {
+    /// seeds the random layout, a fresh layout every run when unset
+    pub(crate) seed: Option<u64>,
+    pub(crate) num_bodies: i32,
+    /// generate just the cloud of bodies, without a sun in the middle
+    pub(crate) no_sun: bool,
+    /// a single satellite on an exact ellipse around the sun instead of the generated bodies
+    pub(crate) two_body: Option<TwoBody>,
+    pub(crate) initial_velocity: InitialVelocity,
+    /// added on top of the initial velocity of generated bodies, e.g. a shearing flow
+    pub(crate) velocity_bias: Option<VelocityBias>,
+    /// how deep two bodies must overlap, as a fraction of the smaller radius, before they merge
+    pub(crate) merge_overlap_fraction: f64,
+    /// nudge merged bodies along their orbit so the angular momentum about the sun stays what the
+    /// parts had, plain momentum averaging changes it a little as the survivor keeps its own position
+    pub(crate) conserve_angular_momentum: bool,
+    /// distribution the masses of generated bodies are drawn from, uniform between 1 and
+    /// `BODY_INITIAL_MASS_MAX` when unset
+    pub(crate) mass_spectrum: Option<MassSpectrum>,
+    /// side length of the broadphase grid cells
+    pub(crate) broadphase_cell_size: f64,
+    /// bodies whose surfaces are this close already count as touching,
+    /// so grazing contacts aren't stepped over with long time steps
+    pub(crate) contact_margin: f64,
+    /// draw each body's name next to it
+    pub(crate) labels: bool,
+    /// give every body a color worked out from its id instead of drawing them all white
+    pub(crate) id_colors: bool,
+    pub(crate) collision_mode: CollisionMode,
+    /// whether mass fills a sphere or a flat disk, sets how big bodies are and how they grow when merging
+    pub(crate) radius_model: RadiusModel,
+    /// lets the sun keep the mass it swallows, growing until it's this heavy and only getting
+    /// heavier after that, the sun never changes when unset
+    pub(crate) compact_sun_mass: Option<f64>,
+    /// bodies further apart than this don't attract each other
+    pub(crate) gravity_cutoff: Option<f64>,
+    /// approximate gravity with a Barnes-Hut tree from this many bodies up, always exact when unset
+    /// the cutoff takes precedence when both are set
+    pub(crate) tree_gravity_threshold: Option<usize>,
+    /// integrate in a frame turning with the two heaviest bodies, which are held still in it
+    /// while everything else feels the centrifugal and coriolis forces of the turning
+    pub(crate) rotating_frame: bool,
+    /// start paused so the initial layout can be inspected and edited
+    pub(crate) start_paused: bool,
+    /// pixels a click may land outside a body's edge and still select it
+    pub(crate) pick_tolerance: f64,
+    /// pause right before the first collision of a selected body, to look at what led up to it
+    pub(crate) break_on_collision: bool,
+    /// pause once the system has settled, so a long run stops burning cpu when nothing's left to happen
+    pub(crate) equilibrium: Option<Equilibrium>,
+    /// milliseconds per frame spent predicting the selected body's orbit while paused,
+    /// the whole prediction is done at once when unset
+    pub(crate) prediction_budget: Option<f64>,
+    /// physics steps the selected body's orbit is predicted ahead while paused
+    pub(crate) prediction_steps: usize,
+    /// a point of the predicted orbit is kept every this many steps
+    pub(crate) prediction_sample_interval: NonZeroUsize,
+    /// predict with only the selected bodies and this many of the heaviest others, the sun included,
+    /// as the rest barely bend the orbit, every body is stepped when unset
+    pub(crate) prediction_body_limit: Option<usize>,
+    /// while paused, the prediction keeps where every body will be at each sampled step,
+    /// so the view can be scrubbed through the predicted future
+    pub(crate) inspect: bool,
+    /// physics steps per tick, each covering an equal share of the tick
+    pub(crate) substeps: u32,
+    /// smooth potential every body orbits in, on top of the pull of the other bodies
+    pub(crate) background_potential: Option<BackgroundPotential>,
+    /// swallowed bodies leave inert debris riding along with whoever swallowed them
+    pub(crate) debris: bool,
+    /// threads used to add up the diagnostics, the totals come out the same for any count
+    pub(crate) diagnostic_threads: usize,
+    /// bodies within `sqrt(sun_luminosity)` of the sun are fully lit, None draws everything plain white
+    pub(crate) sun_luminosity: Option<f64>,
+    /// gravity is computed as if bodies closer than this were this far apart, 0 disables it
+    pub(crate) min_separation: f64,
+    /// pixels left free around the bodies when fitting them all on screen
+    pub(crate) overview_margin: f64,
+    /// draw bodies in between physics ticks instead of where the last tick left them
+    pub(crate) interpolate: bool,
+    /// spreads big merge cascades over several ticks, overlaps past the cap wait for the next tick
+    pub(crate) max_merges_per_tick: Option<usize>,
+    /// merging stops once this few bodies are left, sun included, so a long demo keeps its field
+    pub(crate) min_bodies: Option<usize>,
+    /// bodies from added scenarios and comets appear at most this many per tick,
+    /// the rest wait their turn
+    pub(crate) max_spawns_per_tick: Option<NonZeroUsize>,
+    /// run this many ticks without a window and print where it ended up
+    pub(crate) headless_ticks: Option<u64>,
+    /// wall clock seconds a headless run may take before it stops early
+    pub(crate) time_limit: Option<f64>,
+    /// log how a headless run is doing every this many ticks, nothing is logged when unset
+    pub(crate) log_interval: Option<NonZeroU64>,
+    /// positions, velocities and sizes are rounded to a multiple of this before hashing a run's end state,
+    /// so float noise below it doesn't change the hash
+    pub(crate) state_hash_precision: f64,
+    /// json file listing the bodies to start with, bodies are generated when unset
+    pub(crate) scenario: Option<PathBuf>,
+    /// scenarios whose bodies are added on top of whatever the world started with, in order
+    pub(crate) added_scenarios: Vec<PathBuf>,
+    /// where the bodies are written as a scenario when the app is quit
+    pub(crate) save_on_exit: Option<PathBuf>,
+    /// jsonl file every merge and accretion is appended to, with the tick it happened in
+    pub(crate) event_log: Option<PathBuf>,
+    /// gzipped binary file every body's state is written to after each tick, see `archive::read_archive`
+    pub(crate) trajectory_archive: Option<PathBuf>,
+    /// how gravity is smoothed out at short range, None is plain newtonian
+    pub(crate) softening: Option<Softening>,
+    /// pull of the cursor while the attractor key is held
+    pub(crate) cursor_attractor: CursorAttractor,
+    /// draws a glow this many times the sun's radius behind it
+    pub(crate) corona: Option<f64>,
+    /// outlines the sun's hill sphere, where its pull wins over the primary it's imagined to circle
+    pub(crate) hill_sphere: Option<HillSphere>,
+    /// process bodies in a seeded random order every tick, to expose order dependent results
+    pub(crate) shuffle_update_order: bool,
+    /// add up the pulls on each body in order of the pulling body's id, instead of whatever order
+    /// the world hands them out in, so a seeded run ends up the same everywhere
+    pub(crate) deterministic_sum: bool,
+    /// leave a fading ring, sized by the impact energy, where bodies merge
+    pub(crate) shockwaves: bool,
+    /// what's kept in the middle of the screen, cycle through them with <R>
+    pub(crate) frame_of_reference: FrameOfReference,
+    /// bodies are never drawn smaller than this many pixels across the radius, whatever the zoom
+    pub(crate) min_render_radius: f32,
+    /// bodies with a smaller radius than this many pixels on screen are drawn as a single pixel,
+    /// which is a lot cheaper in a dense field, 0 draws every body as a circle
+    pub(crate) point_render_radius: f32,
+    /// draw the predicted orbit as a line this many pixels wide instead of a trail of dots
+    pub(crate) orbit_line_width: Option<f32>,
+    /// draw where selected bodies have been over this many ticks, only selected bodies are tracked
+    /// and a body's trail is dropped as soon as it's deselected
+    pub(crate) trail_length: Option<usize>,
+    /// smooth out jagged edges, at some cost in frame rate
+    pub(crate) antialiasing: bool,
+    /// sleep between frames to draw at most this many per second, physics still keeps its own rate
+    pub(crate) max_fps: Option<f32>,
+    /// tint bodies with enough energy to escape the sun, None leaves them their usual color
+    pub(crate) escape_color: Option<Rgb>,
+    /// the sun shifts toward a hotter color as it swallows mass, like a protostar brightening up
+    pub(crate) sun_heating: Option<SunHeating>,
+    /// colors bodies by how long they've been around, to tell fresh comets from old ones
+    pub(crate) age_colors: Option<AgeColors>,
+    /// caps how hard gravity can pull a body in one step, so a single close pass can't fling it away
+    pub(crate) max_acceleration: Option<f64>,
+    /// starts gravity out weak and strengthens it over the first ticks, so a random layout
+    /// settles instead of slinging bodies around straight away
+    pub(crate) gravity_ramp: Option<GravityRamp>,
+    /// steers merged bodies close to the sun onto circular orbits, settling them into a disk over time
+    pub(crate) accretion_disk: Option<AccretionDisk>,
+    /// bodies touching the sun slowly enough skim along its surface instead of falling in,
+    /// ending up on a decaying orbit, everything touching the sun is swallowed when unset
+    pub(crate) sun_capture: Option<SunCapture>,
+    /// keeps sending short lived comets past the sun, so the field never goes quiet
+    pub(crate) comets: Option<Comets>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            seed: None,
+            num_bodies: NUM_BODIES,
+            no_sun: false,
+            two_body: None,
+            initial_velocity: InitialVelocity::Random {
+                speed: INITIAL_SPEED as f64,
+            },
+            velocity_bias: None,
+            merge_overlap_fraction: 0.,
+            conserve_angular_momentum: false,
+            mass_spectrum: None,
+            broadphase_cell_size: 20.,
+            contact_margin: 0.,
+            labels: false,
+            id_colors: false,
+            collision_mode: CollisionMode::Merge,
+            radius_model: RadiusModel::Sphere,
+            compact_sun_mass: None,
+            gravity_cutoff: None,
+            tree_gravity_threshold: None,
+            rotating_frame: false,
+            start_paused: false,
+            pick_tolerance: 5.,
+            break_on_collision: false,
+            equilibrium: None,
+            prediction_budget: None,
+            prediction_steps: 10000,
+            prediction_sample_interval: NonZeroUsize::new(100).unwrap(),
+            prediction_body_limit: None,
+            inspect: false,
+            substeps: 1,
+            background_potential: None,
+            debris: false,
+            diagnostic_threads: 1,
+            sun_luminosity: None,
+            min_separation: 0.,
+            overview_margin: 40.,
+            interpolate: false,
+            max_merges_per_tick: None,
+            min_bodies: None,
+            max_spawns_per_tick: None,
+            headless_ticks: None,
+            time_limit: None,
+            log_interval: None,
+            state_hash_precision: 1e-6,
+            scenario: None,
+            added_scenarios: vec![],
+            save_on_exit: None,
+            event_log: None,
+            trajectory_archive: None,
+            softening: None,
+            cursor_attractor: CursorAttractor {
+                strength: 20000.,
+                falloff: 1.,
+            },
+            corona: None,
+            hill_sphere: None,
+            shuffle_update_order: false,
+            deterministic_sum: false,
+            shockwaves: false,
+            frame_of_reference: FrameOfReference::Screen,
+            min_render_radius: 0.,
+            point_render_radius: 0.,
+            orbit_line_width: None,
+            trail_length: None,
+            antialiasing: false,
+            max_fps: None,
+            escape_color: None,
+            sun_heating: None,
+            age_colors: None,
+            max_acceleration: None,
+            gravity_ramp: None,
+            accretion_disk: None,
+            sun_capture: None,
+            comets: None,
+        }
+    }
+}
+
+impl Config {
+    /// parses `--flag value` pairs, anything not mentioned keeps its default
+    pub(crate) fn from_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            match flag.as_str() {
+                "--scenario" => config.scenario = Some(value(&flag, &mut args)?),
+                "--add-scenario" => config.added_scenarios.push(value(&flag, &mut args)?),
+                "--save-on-exit" => config.save_on_exit = Some(value(&flag, &mut args)?),
+                "--seed" => config.seed = Some(value(&flag, &mut args)?),
+                "--bodies" => config.num_bodies = value(&flag, &mut args)?,
+                "--no-sun" => config.no_sun = true,
+                "--two-body" => config.two_body = Some(value(&flag, &mut args)?),
+                "--orbital-velocity" => {
+                    config.initial_velocity = InitialVelocity::Orbital {
+                        perturbation: value(&flag, &mut args)?,
+                    }
+                }
+                "--velocity-bias" => config.velocity_bias = Some(value(&flag, &mut args)?),
+                "--gravity-ramp" => config.gravity_ramp = Some(value(&flag, &mut args)?),
+                "--max-acceleration" => config.max_acceleration = Some(value(&flag, &mut args)?),
+                "--max-merges-per-tick" => {
+                    config.max_merges_per_tick = Some(value(&flag, &mut args)?)
+                }
+                "--min-bodies" => config.min_bodies = Some(value(&flag, &mut args)?),
+                "--max-spawns-per-tick" => {
+                    config.max_spawns_per_tick = Some(value(&flag, &mut args)?)
+                }
+                "--conserve-angular-momentum" => config.conserve_angular_momentum = true,
+                "--merge-overlap-fraction" => {
+                    config.merge_overlap_fraction = value(&flag, &mut args)?
+                }
+                "--mass-spectrum" => config.mass_spectrum = Some(value(&flag, &mut args)?),
+                "--broadphase-cell-size" => {
+                    config.broadphase_cell_size = positive(&flag, &mut args)?
+                }
+                "--contact-margin" => config.contact_margin = non_negative(&flag, &mut args)?,
+                "--labels" => config.labels = true,
+                "--id-colors" => config.id_colors = true,
+                "--pick-tolerance" => config.pick_tolerance = value(&flag, &mut args)?,
+                "--break-on-collision" => config.break_on_collision = true,
+                "--equilibrium" => config.equilibrium = Some(value(&flag, &mut args)?),
+                "--rotating-frame" => config.rotating_frame = true,
+                "--bounce" => {
+                    config.collision_mode = CollisionMode::Bounce {
+                        restitution: value(&flag, &mut args)?,
+                    }
+                }
+                "--radius-model" => config.radius_model = value(&flag, &mut args)?,
+                "--frame" => config.frame_of_reference = value(&flag, &mut args)?,
+                "--tree-gravity-threshold" => {
+                    config.tree_gravity_threshold = Some(value(&flag, &mut args)?)
+                }
+                "--gravity-cutoff" => config.gravity_cutoff = Some(positive(&flag, &mut args)?),
+                "--start-paused" => config.start_paused = true,
+                "--prediction-budget" => {
+                    config.prediction_budget = Some(positive(&flag, &mut args)?)
+                }
+                "--prediction-steps" => config.prediction_steps = value(&flag, &mut args)?,
+                "--prediction-sample-interval" => {
+                    config.prediction_sample_interval = value(&flag, &mut args)?
+                }
+                "--prediction-body-limit" => {
+                    config.prediction_body_limit = Some(value(&flag, &mut args)?)
+                }
+                "--inspect" => config.inspect = true,
+                "--shockwaves" => config.shockwaves = true,
+                "--shuffle-update-order" => config.shuffle_update_order = true,
+                "--deterministic-sum" => config.deterministic_sum = true,
+                "--softening" => config.softening = Some(value(&flag, &mut args)?),
+                "--substeps" => config.substeps = value(&flag, &mut args)?,
+                "--accretion-disk" => config.accretion_disk = Some(value(&flag, &mut args)?),
+                "--sun-capture" => config.sun_capture = Some(value(&flag, &mut args)?),
+                "--comets" => config.comets = Some(value(&flag, &mut args)?),
+                "--background-potential" => {
+                    config.background_potential = Some(value(&flag, &mut args)?)
+                }
+                "--compact-sun" => config.compact_sun_mass = Some(value(&flag, &mut args)?),
+                "--corona" => config.corona = Some(value(&flag, &mut args)?),
+                "--hill-sphere" => config.hill_sphere = Some(value(&flag, &mut args)?),
+                "--cursor-attractor" => config.cursor_attractor = value(&flag, &mut args)?,
+                "--debris" => config.debris = true,
+                "--event-log" => config.event_log = Some(value(&flag, &mut args)?),
+                "--trajectory-archive" => {
+                    config.trajectory_archive = Some(value(&flag, &mut args)?)
+                }
+                "--escape-color" => config.escape_color = Some(value(&flag, &mut args)?),
+                "--sun-heating" => config.sun_heating = Some(value(&flag, &mut args)?),
+                "--color-by-age" => config.age_colors = Some(value(&flag, &mut args)?),
+                "--headless" => config.headless_ticks = Some(value(&flag, &mut args)?),
+                "--interpolate" => config.interpolate = true,
+                "--diagnostic-threads" => config.diagnostic_threads = value(&flag, &mut args)?,
+                "--log-interval" => config.log_interval = Some(value(&flag, &mut args)?),
+                "--state-hash-precision" => {
+                    config.state_hash_precision = positive(&flag, &mut args)?
+                }
+                "--min-render-radius" => config.min_render_radius = value(&flag, &mut args)?,
+                "--point-render-radius" => config.point_render_radius = value(&flag, &mut args)?,
+                "--trail-length" => config.trail_length = Some(value(&flag, &mut args)?),
+                "--orbit-line-width" => config.orbit_line_width = Some(value(&flag, &mut args)?),
+                "--antialiasing" => config.antialiasing = true,
+                "--max-fps" => config.max_fps = Some(positive(&flag, &mut args)?),
+                "--min-separation" => config.min_separation = value(&flag, &mut args)?,
+                "--overview-margin" => config.overview_margin = value(&flag, &mut args)?,
+                "--time-limit" => config.time_limit = Some(positive(&flag, &mut args)?),
+                "--sun-luminosity" => config.sun_luminosity = Some(value(&flag, &mut args)?),
+                _ => return Err(format!("unknown argument: {}", flag)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+fn value<T: FromStr>(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<T, String> {
+    let value = args
+        .next()
+        .ok_or_else(|| format!("missing value for {}", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {}: {}", flag, value))
+}
+
+/// like `value`, for lengths and such where 0 or less makes no sense
+fn positive<T: FromStr + PartialOrd + Default + Display>(
+    flag: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<T, String> {
+    let value: T = value(flag, args)?;
+    // NaN doesn't compare at all, so it's turned away too
+    if value.partial_cmp(&T::default()) != Some(Ordering::Greater) {
+        return Err(format!("{} has to be positive, got {}", flag, value));
+    }
+    Ok(value)
+}
+
+/// like `value`, for margins and such that can be 0 but not less
+fn non_negative(flag: &str, args: &mut impl Iterator<Item = String>) -> Result<f64, String> {
+    let value: f64 = value(flag, args)?;
+    if value.is_nan() || value < 0. {
+        return Err(format!("{} can't be negative, got {}", flag, value));
+    }
+    Ok(value)
+}
+
+/// How `Core::init` picks the starting velocity of each body
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum InitialVelocity {
+    /// each axis is drawn uniformly from `-speed..speed`
+    Random { speed: f64 },
+    /// circular orbit around the sun, with each axis nudged by up to `perturbation` times the orbital speed
+    Orbital { perturbation: f64 },
+}
+
+/// Orbit of the lone satellite, which starts out at its apoapsis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct TwoBody {
+    pub(crate) semi_major_axis: f64,
+    pub(crate) eccentricity: f64,
+}
+
+/// `<semi_major_axis>:<eccentricity>`
+impl FromStr for TwoBody {
+    type Err = String;
+
+    fn from_str(two_body: &str) -> Result<Self, Self::Err> {
+        let parts = two_body.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [semi_major_axis, eccentricity] => {
+                let semi_major_axis = semi_major_axis
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())?;
+                let eccentricity = eccentricity
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())?;
+                if semi_major_axis <= 0. {
+                    return Err(format!(
+                        "the semi-major axis has to be positive, got {}",
+                        semi_major_axis
+                    ));
+                }
+                if !(0. ..1.).contains(&eccentricity) {
+                    return Err(format!(
+                        "the eccentricity has to be at least 0 and below 1, got {}",
+                        eccentricity
+                    ));
+                }
+                Ok(TwoBody {
+                    semi_major_axis,
+                    eccentricity,
+                })
+            }
+            _ => Err(format!(
+                "expected <semi_major_axis>:<eccentricity>, got {}",
+                two_body
+            )),
+        }
+    }
+}
+
+/// Velocity whose components are each a linear function of where a body starts, measured from the sun
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct VelocityBias {
+    pub(crate) x: Linear,
+    pub(crate) y: Linear,
+}
+
+/// `constant + x * offset_x + y * offset_y`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Linear {
+    pub(crate) constant: f64,
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+}
+
+impl VelocityBias {
+    /// `(vx, vy)` of a body starting `(x, y)` away from the sun
+    pub(crate) fn at(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.x.at(x, y), self.y.at(x, y))
+    }
+}
+
+impl Linear {
+    fn at(&self, x: f64, y: f64) -> f64 {
+        self.constant + self.x * x + self.y * y
+    }
+}
+
+/// `<constant>,<x>,<y>:<constant>,<x>,<y>` for vx and vy, e.g. `0,0,0.5:0,0,0` for a shearing flow
+impl FromStr for VelocityBias {
+    type Err = String;
+
+    fn from_str(bias: &str) -> Result<Self, Self::Err> {
+        let (x, y) = bias
+            .split(':')
+            .map(str::parse::<Linear>)
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter()
+            .collect_tuple()
+            .ok_or_else(|| format!("expected a vx and a vy part, got {}", bias))?;
+        Ok(VelocityBias { x, y })
+    }
+}
+
+/// `<constant>,<x>,<y>`
+impl FromStr for Linear {
+    type Err = String;
+
+    fn from_str(linear: &str) -> Result<Self, Self::Err> {
+        let coefficients = linear
+            .split(',')
+            .map(|coefficient| {
+                coefficient
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        match coefficients.as_slice() {
+            [constant, x, y] => Ok(Linear {
+                constant: *constant,
+                x: *x,
+                y: *y,
+            }),
+            _ => Err(format!("expected <constant>,<x>,<y>, got {}", linear)),
+        }
+    }
+}
+
+/// Gravitational constant going linearly from `start` to `GRAVITATIONAL_CONSTANT` over the first `ticks` ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct GravityRamp {
+    pub(crate) start: f64,
+    pub(crate) ticks: NonZeroU64,
+}
+
+impl GravityRamp {
+    pub(crate) fn constant_at(&self, tick: u64) -> f64 {
+        let progress = (tick as f64 / self.ticks.get() as f64).min(1.);
+        self.start + (GRAVITATIONAL_CONSTANT - self.start) * progress
+    }
+}
+
+/// `<start>:<ticks>`
+impl FromStr for GravityRamp {
+    type Err = String;
+
+    fn from_str(ramp: &str) -> Result<Self, Self::Err> {
+        let parts = ramp.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [start, ticks] => Ok(GravityRamp {
+                start: start.parse::<f64>().map_err(|error| error.to_string())?,
+                ticks: ticks
+                    .parse::<NonZeroU64>()
+                    .map_err(|error| error.to_string())?,
+            }),
+            _ => Err(format!("expected <start>:<ticks>, got {}", ramp)),
+        }
+    }
+}
+
+/// The system counts as settled when nothing has merged for `ticks` ticks
+/// and the kinetic energy moved less than `threshold`, relative to its largest value, over that window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Equilibrium {
+    pub(crate) ticks: NonZeroU64,
+    pub(crate) threshold: f64,
+}
+
+/// `<ticks>:<threshold>`
+impl FromStr for Equilibrium {
+    type Err = String;
+
+    fn from_str(equilibrium: &str) -> Result<Self, Self::Err> {
+        let parts = equilibrium.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [ticks, threshold] => Ok(Equilibrium {
+                ticks: ticks
+                    .parse::<NonZeroU64>()
+                    .map_err(|error| error.to_string())?,
+                threshold: threshold
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())?,
+            }),
+            _ => Err(format!("expected <ticks>:<threshold>, got {}", equilibrium)),
+        }
+    }
+}
+
+/// What happens when two bodies touch
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CollisionMode {
+    /// the bigger body swallows the smaller one
+    Merge,
+    /// bodies bounce off each other, impacts fast enough to have no restitution left merge
+    Bounce { restitution: RestitutionCurve },
+}
+
+/// How much room a body's mass takes up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum RadiusModel {
+    /// mass is volume, merging two equal bodies grows the radius by `cbrt(2)`
+    Sphere,
+    /// mass is area, merging two equal bodies grows the radius by `sqrt(2)`
+    Disk,
+}
+
+impl RadiusModel {
+    /// volume of a sphere or area of a disk
+    pub(crate) fn extent(self, radius: f64) -> f64 {
+        match self {
+            RadiusModel::Sphere => 4. / 3. * PI * radius.powi(3),
+            RadiusModel::Disk => PI * radius.powi(2),
+        }
+    }
+
+    /// radius of the sphere or disk with the given `extent`
+    pub(crate) fn radius(self, extent: f64) -> f64 {
+        match self {
+            RadiusModel::Sphere => (extent / (4. / 3. * PI)).cbrt(),
+            RadiusModel::Disk => (extent / PI).sqrt(),
+        }
+    }
+}
+
+impl FromStr for RadiusModel {
+    type Err = String;
+
+    fn from_str(model: &str) -> Result<Self, Self::Err> {
+        match model {
+            "sphere" => Ok(RadiusModel::Sphere),
+            "disk" => Ok(RadiusModel::Disk),
+            _ => Err(format!("expected sphere or disk, got {}", model)),
+        }
+    }
+}
+
+/// Restitution as a function of closing speed, linear between two `(speed, restitution)` points
+/// and flat outside of them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RestitutionCurve {
+    pub(crate) slow: (f64, f64),
+    pub(crate) fast: (f64, f64),
+}
+
+impl RestitutionCurve {
+    pub(crate) fn at(&self, speed: f64) -> f64 {
+        let (slow_speed, slow_restitution) = self.slow;
+        let (fast_speed, fast_restitution) = self.fast;
+        if speed <= slow_speed {
+            slow_restitution
+        } else if speed >= fast_speed {
+            fast_restitution
+        } else {
+            let progress = (speed - slow_speed) / (fast_speed - slow_speed);
+            slow_restitution + (fast_restitution - slow_restitution) * progress
+        }
+    }
+}
+
+/// `slow_speed:restitution,fast_speed:restitution`, e.g. `10:0.9,100:0`
+impl FromStr for RestitutionCurve {
+    type Err = String;
+
+    fn from_str(curve: &str) -> Result<Self, Self::Err> {
+        let points = curve
+            .split(',')
+            .map(|point| {
+                let (speed, restitution) = point
+                    .split(':')
+                    .collect_tuple()
+                    .ok_or_else(|| format!("expected speed:restitution, got {}", point))?;
+                let speed = speed.parse::<f64>().map_err(|error| error.to_string())?;
+                let restitution = restitution
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())?;
+                Ok((speed, restitution))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        match points.as_slice() {
+            [slow, fast] if slow.0 < fast.0 => Ok(RestitutionCurve {
+                slow: *slow,
+                fast: *fast,
+            }),
+            _ => Err(format!("expected a slow and a faster point, got {}", curve)),
+        }
+    }
+}
+
+/// Empirical cumulative distribution of masses, `(mass, probability of being at most that heavy)`
+/// points with both going up, linear in between
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MassSpectrum {
+    points: Vec<(f64, f64)>,
+}
+
+impl MassSpectrum {
+    /// inverse transform, turns a uniform `0..1` draw into a mass following the spectrum
+    pub(crate) fn mass_at(&self, probability: f64) -> f64 {
+        let above = self
+            .points
+            .iter()
+            .position(|(_, cumulative)| *cumulative >= probability)
+            .unwrap_or(self.points.len() - 1);
+        if above == 0 {
+            return self.points[0].0;
+        }
+        let (light, light_cumulative) = self.points[above - 1];
+        let (heavy, heavy_cumulative) = self.points[above];
+        let progress = (probability - light_cumulative) / (heavy_cumulative - light_cumulative);
+        light + (heavy - light) * progress.clamp(0., 1.)
+    }
+
+    pub(crate) fn heaviest(&self) -> f64 {
+        self.points[self.points.len() - 1].0
+    }
+}
+
+/// `mass:cumulative,mass:cumulative,...`, e.g. `1:0,4:0.9,40:1`, the last point has to be at 1
+impl FromStr for MassSpectrum {
+    type Err = String;
+
+    fn from_str(spectrum: &str) -> Result<Self, Self::Err> {
+        let points = spectrum
+            .split(',')
+            .map(|point| {
+                let (mass, cumulative) = point
+                    .split(':')
+                    .collect_tuple()
+                    .ok_or_else(|| format!("expected mass:cumulative, got {}", point))?;
+                let mass = mass.parse::<f64>().map_err(|error| error.to_string())?;
+                let cumulative = cumulative
+                    .parse::<f64>()
+                    .map_err(|error| error.to_string())?;
+                Ok((mass, cumulative))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let increasing = points
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.0 < b.0 && a.1 < b.1);
+        match (points.first(), points.last()) {
+            (Some(first), Some(last))
+                if points.len() > 1
+                    && increasing
+                    && first.0 > 0.
+                    && first.1 >= 0.
+                    && last.1 == 1. =>
+            {
+                Ok(MassSpectrum { points })
+            }
+            _ => Err(format!(
+                "expected positive masses with probabilities rising to 1, got {}",
+                spectrum
+            )),
+        }
+    }
+}
+
+/// Potential of matter that isn't made of bodies, like a galactic halo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum BackgroundPotential {
+    /// `½ v² ln(r² + core²)`, gives the same circular speed at every radius well outside the core
+    Logarithmic {
+        circular_speed: f64,
+        core_radius: f64,
+    },
+    /// an invisible point mass
+    PointMass { mass: f64 },
+}
+
+/// `log:<circular_speed>:<core_radius>` or `point:<mass>`
+impl FromStr for BackgroundPotential {
+    type Err = String;
+
+    fn from_str(potential: &str) -> Result<Self, Self::Err> {
+        let parts = potential.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            ["log", circular_speed, core_radius] => Ok(BackgroundPotential::Logarithmic {
+                circular_speed: number(circular_speed)?,
+                core_radius: number(core_radius)?,
+            }),
+            ["point", mass] => Ok(BackgroundPotential::PointMass {
+                mass: number(mass)?,
+            }),
+            _ => Err(format!(
+                "expected log:<circular_speed>:<core_radius> or point:<mass>, got {}",
+                potential
+            )),
+        }
+    }
+}
+
+/// What the view follows, only changes where things are drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum FrameOfReference {
+    /// draw things where they are, the camera keys move the view
+    Screen,
+    Sun,
+    /// mass weighted center of every body, sun included
+    Barycenter,
+}
+
+impl FrameOfReference {
+    pub(crate) fn next(self) -> FrameOfReference {
+        match self {
+            FrameOfReference::Screen => FrameOfReference::Sun,
+            FrameOfReference::Sun => FrameOfReference::Barycenter,
+            FrameOfReference::Barycenter => FrameOfReference::Screen,
+        }
+    }
+}
+
+impl FromStr for FrameOfReference {
+    type Err = String;
+
+    fn from_str(frame: &str) -> Result<Self, Self::Err> {
+        match frame {
+            "screen" => Ok(FrameOfReference::Screen),
+            "sun" => Ok(FrameOfReference::Sun),
+            "barycenter" => Ok(FrameOfReference::Barycenter),
+            _ => Err(format!("expected screen, sun or barycenter, got {}", frame)),
+        }
+    }
+}
+
+/// Acceleration toward the cursor, `strength / distance^falloff`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CursorAttractor {
+    /// negative pushes bodies away
+    pub(crate) strength: f64,
+    pub(crate) falloff: f64,
+}
+
+/// `<strength>:<falloff>`
+impl FromStr for CursorAttractor {
+    type Err = String;
+
+    fn from_str(attractor: &str) -> Result<Self, Self::Err> {
+        let parts = attractor.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            [strength, falloff] => Ok(CursorAttractor {
+                strength: number(strength)?,
+                falloff: number(falloff)?,
+            }),
+            _ => Err(format!("expected <strength>:<falloff>, got {}", attractor)),
+        }
+    }
+}
+
+/// Kernel that keeps gravity finite when bodies get close
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Softening {
+    /// `r² + length²` in the denominator, slightly weaker than newtonian at every distance
+    Plummer { length: f64 },
+    /// cubic spline, exactly newtonian from `length` out
+    Spline { length: f64 },
+}
+
+/// `plummer:<length>` or `spline:<length>`
+impl FromStr for Softening {
+    type Err = String;
+
+    fn from_str(softening: &str) -> Result<Self, Self::Err> {
+        let parts = softening.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            ["plummer", length] => Ok(Softening::Plummer {
+                length: number(length)?,
+            }),
+            ["spline", length] => Ok(Softening::Spline {
+                length: number(length)?,
+            }),
+            _ => Err(format!(
+                "expected plummer:<length> or spline:<length>, got {}",
+                softening
+            )),
+        }
+    }
+}
+
+/// Nudge toward a circular orbit that bodies get when they merge close to the sun
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct AccretionDisk {
+    /// merges further than this from the sun are left alone
+    pub(crate) radius: f64,
+    /// 0 keeps the merged velocity, 1 replaces it with the circular orbit velocity
+    pub(crate) nudge: f64,
+}
+
+/// `<radius>:<nudge>`
+impl FromStr for AccretionDisk {
+    type Err = String;
+
+    fn from_str(disk: &str) -> Result<Self, Self::Err> {
+        let parts = disk.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            [radius, nudge] => Ok(AccretionDisk {
+                radius: number(radius)?,
+                nudge: number(nudge)?,
+            }),
+            _ => Err(format!("expected <radius>:<nudge>, got {}", disk)),
+        }
+    }
+}
+
+/// A primary the sun circles, e.g. the center of its galaxy, only used to size the sun's hill sphere
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct HillSphere {
+    pub(crate) primary_mass: f64,
+    /// from the sun to the primary
+    pub(crate) distance: f64,
+}
+
+/// `<primary_mass>:<distance>`
+impl FromStr for HillSphere {
+    type Err = String;
+
+    fn from_str(sphere: &str) -> Result<Self, Self::Err> {
+        let parts = sphere.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            [primary_mass, distance] => {
+                let (primary_mass, distance) = (number(primary_mass)?, number(distance)?);
+                if primary_mass <= 0. || distance <= 0. {
+                    return Err(format!(
+                        "the mass and distance have to be positive, got {}",
+                        sphere
+                    ));
+                }
+                Ok(HillSphere {
+                    primary_mass,
+                    distance,
+                })
+            }
+            _ => Err(format!(
+                "expected <primary_mass>:<distance>, got {}",
+                sphere
+            )),
+        }
+    }
+}
+
+/// What happens to bodies that brush the sun instead of diving into it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SunCapture {
+    /// bodies slower than this relative to the sun are captured, faster ones are still swallowed
+    pub(crate) speed: f64,
+    /// share of the speed along the surface a captured body loses, 0 to 1
+    pub(crate) damping: f64,
+}
+
+/// `<speed>:<damping>`
+impl FromStr for SunCapture {
+    type Err = String;
+
+    fn from_str(capture: &str) -> Result<Self, Self::Err> {
+        let parts = capture.split(':').collect::<Vec<_>>();
+        let number = |part: &str| part.parse::<f64>().map_err(|error| error.to_string());
+        match parts.as_slice() {
+            [speed, damping] => {
+                let damping = number(damping)?;
+                if !(0. ..=1.).contains(&damping) {
+                    return Err(format!(
+                        "damping has to be between 0 and 1, got {}",
+                        damping
+                    ));
+                }
+                Ok(SunCapture {
+                    speed: number(speed)?,
+                    damping,
+                })
+            }
+            _ => Err(format!("expected <speed>:<damping>, got {}", capture)),
+        }
+    }
+}
+
+/// A comet every so often, on a long thin orbit around the sun
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Comets {
+    /// ticks between two comets
+    pub(crate) interval: NonZeroU64,
+    pub(crate) lifetime: CometLifetime,
+}
+
+/// When a comet is removed again, unless something swallows it first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum CometLifetime {
+    Ticks(u64),
+    /// right after its closest approach to the sun
+    Flyby,
+}
+
+/// `<interval>:<ticks>` or `<interval>:flyby`
+impl FromStr for Comets {
+    type Err = String;
+
+    fn from_str(comets: &str) -> Result<Self, Self::Err> {
+        let parts = comets.split(':').collect::<Vec<_>>();
+        let interval = |part: &str| {
+            part.parse::<NonZeroU64>()
+                .map_err(|error| error.to_string())
+        };
+        match parts.as_slice() {
+            [every, "flyby"] => Ok(Comets {
+                interval: interval(every)?,
+                lifetime: CometLifetime::Flyby,
+            }),
+            [every, ticks] => Ok(Comets {
+                interval: interval(every)?,
+                lifetime: CometLifetime::Ticks(
+                    ticks.parse::<u64>().map_err(|error| error.to_string())?,
+                ),
+            }),
+            _ => Err(format!(
+                "expected <interval>:<ticks> or <interval>:flyby, got {}",
+                comets
+            )),
+        }
+    }
+}
+
+/// Color ramp of the sun, from its own color to `color` once it has swallowed `full_at` mass
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SunHeating {
+    pub(crate) full_at: f64,
+    pub(crate) color: Rgb,
+}
+
+/// `<mass>:<r>,<g>,<b>`, e.g. `500:255,255,255`
+impl FromStr for SunHeating {
+    type Err = String;
+
+    fn from_str(heating: &str) -> Result<Self, Self::Err> {
+        let parts = heating.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [full_at, color] => {
+                let full_at = full_at.parse::<f64>().map_err(|error| error.to_string())?;
+                if full_at <= 0. {
+                    return Err(format!("the mass has to be positive, got {}", full_at));
+                }
+                Ok(SunHeating {
+                    full_at,
+                    color: color.parse()?,
+                })
+            }
+            _ => Err(format!("expected <mass>:<r>,<g>,<b>, got {}", heating)),
+        }
+    }
+}
+
+/// Gradient a body's color runs through as it ages, the colors spread evenly over the first
+/// `old_at` ticks and the last one kept after that
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct AgeColors {
+    pub(crate) old_at: NonZeroU64,
+    /// at least two, newest first
+    pub(crate) palette: Vec<Rgb>,
+}
+
+/// `<ticks>:<r>,<g>,<b>:<r>,<g>,<b>[:<r>,<g>,<b>...]`, newest color first
+impl FromStr for AgeColors {
+    type Err = String;
+
+    fn from_str(colors: &str) -> Result<Self, Self::Err> {
+        let parts = colors.split(':').collect::<Vec<_>>();
+        match parts.as_slice() {
+            [old_at, palette @ ..] if palette.len() >= 2 => Ok(AgeColors {
+                old_at: old_at
+                    .parse::<NonZeroU64>()
+                    .map_err(|error| error.to_string())?,
+                palette: palette
+                    .iter()
+                    .map(|color| color.parse())
+                    .collect::<Result<_, _>>()?,
+            }),
+            _ => Err(format!(
+                "expected <ticks>:<r>,<g>,<b>:<r>,<g>,<b>, got {}",
+                colors
+            )),
+        }
+    }
+}
+
+/// 8 bit color channels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Rgb(pub(crate) [u8; 3]);
+
+/// `<r>,<g>,<b>`, e.g. `255,80,80`
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        let channels = color
+            .split(',')
+            .map(|channel| channel.parse::<u8>().map_err(|error| error.to_string()))
+            .collect::<Result<Vec<_>, String>>()?;
+        match channels.as_slice() {
+            [r, g, b] => Ok(Rgb([*r, *g, *b])),
+            _ => Err(format!("expected <r>,<g>,<b>, got {}", color)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn no_arguments_gives_default_config() {
+        assert_eq!(Config::from_args(vec![]), Ok(Config::default()));
+    }
+
+    #[test]
+    fn parses_orbital_velocity() {
+        let config = Config::from_args(args(&["--orbital-velocity", "0.1"])).unwrap();
+        assert_eq!(
+            config.initial_velocity,
+            InitialVelocity::Orbital { perturbation: 0.1 }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert!(Config::from_args(args(&["--orbital-velocity"])).is_err());
+        assert!(Config::from_args(args(&["--orbital-velocity", "fast"])).is_err());
+        assert!(Config::from_args(args(&["--warp-speed"])).is_err());
+    }
+
+    #[test]
+    fn restitution_curve_interpolates_between_its_points() {
+        let curve: RestitutionCurve = "10:0.9,110:0.1".parse().unwrap();
+        assert_eq!(curve.at(0.), 0.9);
+        assert_eq!(curve.at(10.), 0.9);
+        assert!((curve.at(60.) - 0.5).abs() < 1e-12);
+        assert!((curve.at(35.) - 0.7).abs() < 1e-12);
+        assert_eq!(curve.at(110.), 0.1);
+        assert_eq!(curve.at(1000.), 0.1);
+    }
+
+    #[test]
+    fn rejects_malformed_restitution_curves() {
+        assert!("10:0.9".parse::<RestitutionCurve>().is_err());
+        assert!("100:0.9,10:0".parse::<RestitutionCurve>().is_err());
+        assert!("10-0.9,100-0".parse::<RestitutionCurve>().is_err());
+    }
+
+    #[test]
+    fn parses_background_potentials() {
+        let config = Config::from_args(args(&["--background-potential", "log:30:5"])).unwrap();
+        assert_eq!(
+            config.background_potential,
+            Some(BackgroundPotential::Logarithmic {
+                circular_speed: 30.,
+                core_radius: 5.
+            })
+        );
+        assert_eq!(
+            "point:500".parse(),
+            Ok(BackgroundPotential::PointMass { mass: 500. })
+        );
+        assert!("disk:5".parse::<BackgroundPotential>().is_err());
+    }
+
+    #[test]
+    fn parses_softening_kernels() {
+        let config = Config::from_args(args(&["--softening", "spline:4"])).unwrap();
+        assert_eq!(config.softening, Some(Softening::Spline { length: 4. }));
+        assert_eq!(
+            "plummer:0.5".parse(),
+            Ok(Softening::Plummer { length: 0.5 })
+        );
+        assert!("gaussian:1".parse::<Softening>().is_err());
+        assert!("spline".parse::<Softening>().is_err());
+    }
+
+    #[test]
+    fn parses_cursor_attractor() {
+        let config = Config::from_args(args(&["--cursor-attractor", "-500:2"])).unwrap();
+        assert_eq!(
+            config.cursor_attractor,
+            CursorAttractor {
+                strength: -500.,
+                falloff: 2.
+            }
+        );
+        assert!("500".parse::<CursorAttractor>().is_err());
+    }
+
+    #[test]
+    fn parses_escape_color() {
+        let config = Config::from_args(args(&["--escape-color", "255,80,0"])).unwrap();
+        assert_eq!(config.escape_color, Some(Rgb([255, 80, 0])));
+        assert!("255,80".parse::<Rgb>().is_err());
+        assert!("256,0,0".parse::<Rgb>().is_err());
+    }
+
+    #[test]
+    fn log_interval_must_be_positive() {
+        let config = Config::from_args(args(&["--log-interval", "500"])).unwrap();
+        assert_eq!(config.log_interval, NonZeroU64::new(500));
+        assert!(Config::from_args(args(&["--log-interval", "0"])).is_err());
+    }
+
+    #[test]
+    fn broadphase_cell_size_must_be_positive() {
+        let config = Config::from_args(args(&["--broadphase-cell-size", "12.5"])).unwrap();
+        assert_eq!(config.broadphase_cell_size, 12.5);
+        assert!(Config::from_args(args(&["--broadphase-cell-size", "0"])).is_err());
+        assert!(Config::from_args(args(&["--broadphase-cell-size", "-20"])).is_err());
+    }
+
+    #[test]
+    fn contact_margin_must_not_be_negative() {
+        let config = Config::from_args(args(&["--contact-margin", "0"])).unwrap();
+        assert_eq!(config.contact_margin, 0.);
+        assert!(Config::from_args(args(&["--contact-margin", "-0.5"])).is_err());
+        assert!(Config::from_args(args(&["--contact-margin", "NaN"])).is_err());
+    }
+
+    #[test]
+    fn time_limit_must_be_positive() {
+        let config = Config::from_args(args(&["--time-limit", "2.5"])).unwrap();
+        assert_eq!(config.time_limit, Some(2.5));
+        assert!(Config::from_args(args(&["--time-limit", "-1"])).is_err());
+        assert!(Config::from_args(args(&["--time-limit", "nan"])).is_err());
+    }
+
+    #[test]
+    fn max_fps_must_be_positive() {
+        let config = Config::from_args(args(&["--max-fps", "30"])).unwrap();
+        assert_eq!(config.max_fps, Some(30.));
+        assert!(Config::from_args(args(&["--max-fps", "0"])).is_err());
+        assert!(Config::from_args(args(&["--max-fps", "-60"])).is_err());
+        assert!(Config::from_args(args(&["--max-fps", "NaN"])).is_err());
+    }
+
+    #[test]
+    fn state_hash_precision_must_be_positive() {
+        let config = Config::from_args(args(&["--state-hash-precision", "1e-3"])).unwrap();
+        assert_eq!(config.state_hash_precision, 1e-3);
+        assert!(Config::from_args(args(&["--state-hash-precision", "0"])).is_err());
+        assert!(Config::from_args(args(&["--state-hash-precision", "-1e-6"])).is_err());
+        assert!(Config::from_args(args(&["--state-hash-precision", "NaN"])).is_err());
+    }
+
+    #[test]
+    fn prediction_budget_must_be_positive() {
+        let config = Config::from_args(args(&["--prediction-budget", "4"])).unwrap();
+        assert_eq!(config.prediction_budget, Some(4.));
+        assert!(Config::from_args(args(&["--prediction-budget", "-4"])).is_err());
+        assert!(Config::from_args(args(&["--prediction-budget", "NaN"])).is_err());
+    }
+
+    #[test]
+    fn spawn_cap_must_let_something_through() {
+        let config = Config::from_args(args(&["--max-spawns-per-tick", "3"])).unwrap();
+        assert_eq!(config.max_spawns_per_tick, NonZeroUsize::new(3));
+        assert!(Config::from_args(args(&["--max-spawns-per-tick", "0"])).is_err());
+    }
+
+    #[test]
+    fn gravity_cutoff_must_be_positive() {
+        let config = Config::from_args(args(&["--gravity-cutoff", "300"])).unwrap();
+        assert_eq!(config.gravity_cutoff, Some(300.));
+        assert!(Config::from_args(args(&["--gravity-cutoff", "0"])).is_err());
+        assert!(Config::from_args(args(&["--gravity-cutoff", "-1"])).is_err());
+    }
+
+    #[test]
+    fn parses_accretion_disk() {
+        let config = Config::from_args(args(&["--accretion-disk", "250:0.1"])).unwrap();
+        assert_eq!(
+            config.accretion_disk,
+            Some(AccretionDisk {
+                radius: 250.,
+                nudge: 0.1
+            })
+        );
+        assert!("250".parse::<AccretionDisk>().is_err());
+    }
+
+    #[test]
+    fn parses_sun_capture() {
+        let config = Config::from_args(args(&["--sun-capture", "30:0.2"])).unwrap();
+        assert_eq!(
+            config.sun_capture,
+            Some(SunCapture {
+                speed: 30.,
+                damping: 0.2
+            })
+        );
+        assert!("30".parse::<SunCapture>().is_err());
+        assert!("30:1.5".parse::<SunCapture>().is_err());
+    }
+
+    #[test]
+    fn parses_comets() {
+        let config = Config::from_args(args(&["--comets", "600:flyby"])).unwrap();
+        assert_eq!(
+            config.comets,
+            Some(Comets {
+                interval: NonZeroU64::new(600).unwrap(),
+                lifetime: CometLifetime::Flyby
+            })
+        );
+        assert_eq!(
+            "60:900".parse::<Comets>(),
+            Ok(Comets {
+                interval: NonZeroU64::new(60).unwrap(),
+                lifetime: CometLifetime::Ticks(900)
+            })
+        );
+        assert!("0:flyby".parse::<Comets>().is_err());
+        assert!("60".parse::<Comets>().is_err());
+        assert!("60:soon".parse::<Comets>().is_err());
+    }
+
+    #[test]
+    fn parses_velocity_bias() {
+        let config = Config::from_args(args(&["--velocity-bias", "1,0,0.5:0,-2,0"])).unwrap();
+        let bias = config.velocity_bias.unwrap();
+        assert_eq!(
+            bias,
+            VelocityBias {
+                x: Linear {
+                    constant: 1.,
+                    x: 0.,
+                    y: 0.5
+                },
+                y: Linear {
+                    constant: 0.,
+                    x: -2.,
+                    y: 0.
+                },
+            }
+        );
+        assert_eq!(bias.at(10., 20.), (11., -20.));
+        assert!("1,0,0.5".parse::<VelocityBias>().is_err());
+        assert!("1,0:0,0,0".parse::<VelocityBias>().is_err());
+        assert!("1,0,0:0,0,0:0,0,0".parse::<VelocityBias>().is_err());
+    }
+
+    #[test]
+    fn parses_hill_sphere() {
+        let config = Config::from_args(args(&["--hill-sphere", "1e9:5000"])).unwrap();
+        assert_eq!(
+            config.hill_sphere,
+            Some(HillSphere {
+                primary_mass: 1e9,
+                distance: 5000.
+            })
+        );
+        assert!("1e9".parse::<HillSphere>().is_err());
+        assert!("1e9:-5".parse::<HillSphere>().is_err());
+    }
+
+    #[test]
+    fn gravity_ramps_up_to_the_constant() {
+        let config = Config::from_args(args(&["--gravity-ramp", "0.5:100"])).unwrap();
+        let ramp = config.gravity_ramp.unwrap();
+        assert_eq!(ramp.constant_at(0), 0.5);
+        assert_eq!(ramp.constant_at(50), (0.5 + GRAVITATIONAL_CONSTANT) / 2.);
+        assert_eq!(ramp.constant_at(100), GRAVITATIONAL_CONSTANT);
+        assert_eq!(ramp.constant_at(5000), GRAVITATIONAL_CONSTANT);
+        assert!("0.5:0".parse::<GravityRamp>().is_err());
+        assert!("0.5".parse::<GravityRamp>().is_err());
+    }
+
+    #[test]
+    fn parses_two_body() {
+        let config = Config::from_args(args(&["--two-body", "150:0.5"])).unwrap();
+        assert_eq!(
+            config.two_body,
+            Some(TwoBody {
+                semi_major_axis: 150.,
+                eccentricity: 0.5,
+            })
+        );
+        assert!("150:1".parse::<TwoBody>().is_err());
+        assert!("-150:0.5".parse::<TwoBody>().is_err());
+        assert!("150".parse::<TwoBody>().is_err());
+    }
+
+    #[test]
+    fn parses_radius_model() {
+        assert_eq!(Config::default().radius_model, RadiusModel::Sphere);
+        let config = Config::from_args(args(&["--radius-model", "disk"])).unwrap();
+        assert_eq!(config.radius_model, RadiusModel::Disk);
+        assert!("cube".parse::<RadiusModel>().is_err());
+    }
+
+    #[test]
+    fn parses_equilibrium() {
+        let config = Config::from_args(args(&["--equilibrium", "500:0.01"])).unwrap();
+        assert_eq!(
+            config.equilibrium,
+            Some(Equilibrium {
+                ticks: NonZeroU64::new(500).unwrap(),
+                threshold: 0.01,
+            })
+        );
+        assert!("0:0.01".parse::<Equilibrium>().is_err());
+        assert!("500".parse::<Equilibrium>().is_err());
+    }
+
+    #[test]
+    fn parses_sun_heating() {
+        let config = Config::from_args(args(&["--sun-heating", "500:255,255,255"])).unwrap();
+        assert_eq!(
+            config.sun_heating,
+            Some(SunHeating {
+                full_at: 500.,
+                color: Rgb([255, 255, 255])
+            })
+        );
+        assert!("0:255,255,255".parse::<SunHeating>().is_err());
+        assert!("500".parse::<SunHeating>().is_err());
+        assert!("500:255,255".parse::<SunHeating>().is_err());
+    }
+
+    #[test]
+    fn parses_age_colors() {
+        let config = Config::from_args(args(&[
+            "--color-by-age",
+            "1000:255,255,255:255,128,0:128,0,0",
+        ]))
+        .unwrap();
+        assert_eq!(
+            config.age_colors,
+            Some(AgeColors {
+                old_at: NonZeroU64::new(1000).unwrap(),
+                palette: vec![Rgb([255, 255, 255]), Rgb([255, 128, 0]), Rgb([128, 0, 0])],
+            })
+        );
+        assert!("1000:255,255,255".parse::<AgeColors>().is_err());
+        assert!("0:255,255,255:0,0,0".parse::<AgeColors>().is_err());
+        assert!("1000:255,255,255:0,0".parse::<AgeColors>().is_err());
+    }
+
+    #[test]
+    fn mass_spectrum_sampling_reproduces_its_quantiles() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let config =
+            Config::from_args(args(&["--mass-spectrum", "1:0,2:0.5,10:0.9,50:1"])).unwrap();
+        let spectrum = config.mass_spectrum.unwrap();
+        let mut rng = StdRng::seed_from_u64(8);
+        let masses = (0..20_000)
+            .map(|_| spectrum.mass_at(rng.gen()))
+            .collect::<Vec<_>>();
+
+        let below = |mass: f64| {
+            masses.iter().filter(|sample| **sample <= mass).count() as f64 / masses.len() as f64
+        };
+        for (mass, cumulative) in &[(1., 0.), (2., 0.5), (6., 0.7), (10., 0.9), (50., 1.)] {
+            assert!((below(*mass) - cumulative).abs() < 0.015, "{}", mass);
+        }
+        assert_eq!(spectrum.heaviest(), 50.);
+    }
+
+    #[test]
+    fn rejects_malformed_mass_spectra() {
+        assert!("1:0".parse::<MassSpectrum>().is_err());
+        assert!("1:0,5:0.8".parse::<MassSpectrum>().is_err());
+        assert!("5:0,1:1".parse::<MassSpectrum>().is_err());
+        assert!("1:0.5,5:0.2,9:1".parse::<MassSpectrum>().is_err());
+        assert!("0:0,5:1".parse::<MassSpectrum>().is_err());
+    }
+}
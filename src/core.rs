@@ -1,18 +1,39 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
 use std::f64::consts::PI;
+use std::num::NonZeroUsize;
 use std::ops::Not;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use itertools::Itertools;
 use legion::prelude::*;
 use nalgebra::{Isometry2, Point, Point2, Vector2};
 use ncollide2d::query::{self, PointQuery, Proximity};
 use ncollide2d::shape::Ball;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tracing::{error, warn};
 
-use crate::{
-    BODY_INITIAL_MASS_MAX, GRAVITATIONAL_CONSTANT, HEIGHT, INITIAL_SPEED, NUM_BODIES, SUN_SIZE,
-    WIDTH,
+use crate::archive::TrajectoryArchive;
+use crate::broadphase::SpatialGrid;
+use crate::config::{
+    AgeColors, BackgroundPotential, CollisionMode, CometLifetime, Config, CursorAttractor,
+    FrameOfReference, InitialVelocity, MassSpectrum, RadiusModel, Rgb, Softening, SunHeating,
+    TwoBody,
 };
+use crate::events::{EventLog, SimulationEvent};
+use crate::history::SampleBuffer;
+use crate::ids::IdAllocator;
+use crate::kepler::{
+    hill_radius, is_unbound, orbital_elements, periapsis_distance, state_at_apoapsis,
+    OrbitalElements,
+};
+use crate::quadtree::QuadTree;
+use crate::reduce::pairwise_sum;
+use crate::scenario::{Scenario, ScenarioBody, ScenarioSun};
+use crate::{BODY_INITIAL_MASS_MAX, GRAVITATIONAL_CONSTANT, HEIGHT, SUN_SIZE, UPS, WIDTH};
 
 // Define our entity data types
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -43,9 +64,16 @@ struct MetaInfo {
 }
 
 impl Dimensions {
-    fn from_mass(mass: f64) -> Dimensions {
-        let radius: f64 = mass / (4. / 3. * PI);
-        let radius = radius.cbrt();
+    fn from_mass(mass: f64, model: RadiusModel) -> Dimensions {
+        Dimensions::from_mass_and_density(mass, 1., model)
+    }
+
+    fn density(&self, model: RadiusModel) -> f64 {
+        self.mass / model.extent(self.radius)
+    }
+
+    fn from_mass_and_density(mass: f64, density: f64, model: RadiusModel) -> Dimensions {
+        let radius = model.radius(mass / density);
         Dimensions { mass, radius }
     }
 }
@@ -54,6 +82,7 @@ impl Dimensions {
 struct Data {
     name: String,
     sun: bool,
+    color: Option<[u8; 3]>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -64,104 +93,810 @@ struct Id {
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Model(usize);
 
+/// pinned in place, still pulls on everything else
 #[derive(Clone, Copy, Debug, PartialEq)]
 struct Static;
 
+/// how bouncy a body is in bounce mode, a pair bounces with the restitution curve times both of theirs
+/// bodies without one count as 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Restitution(f64);
+
+/// tick a body was spawned in, bodies put in straight into the world have none
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SpawnTick(u64);
+
+/// only around for a while, comets have one
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Lifetime {
+    /// removed once the tick count gets here
+    UntilTick(u64),
+    /// removed once it's past its closest approach to the sun, `falling` since it started heading in
+    Flyby { falling: bool },
+}
+
+/// which populations a body belongs to and which ones it collides with, as bit sets
+/// bodies without one are in the first category and collide with everything
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct CollisionMask {
+    pub(crate) categories: u32,
+    pub(crate) collides_with: u32,
+}
+
+impl Default for CollisionMask {
+    fn default() -> Self {
+        CollisionMask {
+            categories: 1,
+            collides_with: u32::MAX,
+        }
+    }
+}
+
+impl CollisionMask {
+    /// both sides have to agree
+    fn interacts_with(&self, other: &CollisionMask) -> bool {
+        self.categories & other.collides_with != 0 && other.categories & self.collides_with != 0
+    }
+}
+
+/// inert leftover of a swallowed body, riding along with the body that swallowed it
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Debris {
+    parent: i32,
+    offset: Vector2<f64>,
+}
+
+/// something waiting to be added to the world
+enum PendingSpawn {
+    Body(BodySpec),
+    Comet(CometLifetime),
+}
+
+/// one end of a measurement, a body end follows the body around
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Endpoint {
+    Point(Point2<f64>),
+    Body(i32),
+}
+
+// how often the live graphs take a sample, and how many samples they show
+const GRAPH_SAMPLE_INTERVAL: u64 = 10;
+const GRAPH_SAMPLES: usize = 300;
+
+const DEBRIS_RADIUS: f64 = 0.5;
+
+// what the sun is drawn as when it wasn't given a color
+const SUN_COLOR: [u8; 3] = [255, 255, 0];
+
+// the corona breathes by this fraction of its size, one cycle every 2π / CORONA_PULSE_SPEED ticks
+const CORONA_PULSE: f64 = 0.05;
+const CORONA_PULSE_SPEED: f64 = 0.02;
+
+// shockwaves start at this many pixels per cube root of impact energy, and grow to three times that
+const SHOCKWAVE_SIZE: f64 = 0.5;
+const SHOCKWAVE_GROWTH: f64 = 2.;
+const SHOCKWAVE_TICKS: u32 = 60;
+
+// how far the overview zooms in on a tight cluster
+const MAX_OVERVIEW_ZOOM: f64 = 4.;
+
+// random positions tried for a new body before giving up on it
+const SPAWN_ATTEMPTS: usize = 100;
+
+// comets come in from this far out, on an orbit stretched this much
+const COMET_APOAPSIS: f64 = 280.;
+const COMET_ECCENTRICITY: f64 = 0.9;
+const COMET_MASS: f64 = 1.;
+
+// of the lone satellite in a two body setup
+const SATELLITE_MASS: f64 = 1.;
+
+/// number of physics steps executed so far, paused ticks don't count
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub(crate) struct TickCount(pub(crate) u64);
+
+/// the simulation is paused, so it can't be advanced
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Paused;
+
 pub(crate) struct Core {
     world: World,
+    config: Config,
+    tick_count: TickCount,
     paused: bool,
-    predicted_orbit: Option<Vec<Point2<f64>>>,
+    prediction: Option<Prediction>,
+    broadphase: SpatialGrid,
+    show_broadphase: bool,
+    largest_mass_history: SampleBuffer,
+    show_mass_graph: bool,
+    // distance between the two heaviest bodies apart from the sun
+    separation_history: SampleBuffer,
+    show_separation_graph: bool,
+    // where bodies were before the last tick, to draw them in between ticks
+    previous_positions: HashMap<i32, Point2<f64>>,
+    // the sun always gets -1, everything else an id from here
+    ids: IdAllocator,
+    // where the cursor pulls bodies toward, while the attractor key is held
+    attractor: Option<Point2<f64>>,
+    // only drawn from when shuffling the update order, so it doesn't change the layout
+    shuffle_rng: StdRng,
+    // where comets come from, kept apart for the same reason
+    comet_rng: StdRng,
+    shockwaves: Vec<Shockwave>,
+    frame_of_reference: FrameOfReference,
+    // how far the view has been panned, on top of keeping the frame of reference in the middle
+    camera: Vector2<f64>,
+    // recent positions of the selected bodies, oldest first, when trails are turned on
+    trails: HashMap<i32, VecDeque<Point2<f64>>>,
+    // bodies waiting for room under the spawn cap
+    spawn_queue: VecDeque<PendingSpawn>,
+    // everything the sun has swallowed since the start, heats it up when that's turned on
+    accreted_mass: f64,
+    // the ends clicked so far with the measuring tool, at most two
+    measurement: Vec<Endpoint>,
+    // every contact during the last tick, a pair can touch again in a later substep
+    impacts: Vec<Impact>,
+    event_log: Option<EventLog>,
+    // the end of the gzip stream is written when it's dropped along with the rest
+    trajectory_archive: Option<TrajectoryArchive>,
+    // ticks run before the last reset, so recorded tick numbers keep counting up across resets
+    ticks_before_reset: u64,
+    // pause as soon as a selected body is about to collide, fires once
+    collision_breakpoint: bool,
+    // which predicted snapshot is drawn instead of the world while inspecting, None for the present
+    scrub: Option<usize>,
+    // kinetic energy of every tick since the last merge, at most the equilibrium window's worth
+    kinetic_energy_window: VecDeque<f64>,
 }
 
 impl Core {
-    pub(crate) fn new() -> Core {
+    pub(crate) fn new(config: Config) -> Core {
+        let event_log = config.event_log.clone().map(EventLog::new);
+        let trajectory_archive = config.trajectory_archive.as_ref().and_then(|path| {
+            TrajectoryArchive::create(path)
+                .map_err(|error| warn!("{}, not recording", error))
+                .ok()
+        });
+        Core::with_recorders(config, event_log, trajectory_archive)
+    }
+
+    /// a fresh core that keeps writing to recorders that are already open
+    fn with_recorders(
+        config: Config,
+        event_log: Option<EventLog>,
+        trajectory_archive: Option<TrajectoryArchive>,
+    ) -> Core {
         let universe = Universe::new();
         let world = universe.create_world();
+        let shuffle_rng = seeded_rng(config.seed);
+        let comet_rng = seeded_rng(config.seed);
         Core {
             world,
-            paused: false,
-            predicted_orbit: None,
+            tick_count: TickCount::default(),
+            paused: config.start_paused,
+            collision_breakpoint: config.break_on_collision,
+            frame_of_reference: config.frame_of_reference,
+            config,
+            prediction: None,
+            broadphase: SpatialGrid::default(),
+            show_broadphase: false,
+            largest_mass_history: SampleBuffer::new(GRAPH_SAMPLES),
+            show_mass_graph: false,
+            separation_history: SampleBuffer::new(GRAPH_SAMPLES),
+            show_separation_graph: false,
+            previous_positions: HashMap::new(),
+            ids: IdAllocator::default(),
+            attractor: None,
+            shuffle_rng,
+            comet_rng,
+            shockwaves: vec![],
+            camera: Vector2::zeros(),
+            measurement: vec![],
+            trails: HashMap::new(),
+            spawn_queue: VecDeque::new(),
+            accreted_mass: 0.,
+            impacts: vec![],
+            event_log,
+            trajectory_archive,
+            ticks_before_reset: 0,
+            kinetic_energy_window: VecDeque::new(),
+            scrub: None,
         }
     }
 
     pub(crate) fn init(&mut self) {
-        let mut rng = rand::thread_rng();
+        self.populate();
+        for path in self.config.added_scenarios.clone() {
+            if let Err(error) = self.load_additive(&path) {
+                warn!("{}, leaving it out", error);
+            }
+        }
+    }
+
+    /// the scenario, or generated bodies around a sun
+    fn populate(&mut self) {
+        if let Some(path) = self.config.scenario.clone() {
+            match Scenario::load(&path) {
+                Ok(scenario) => return self.load_scenario(&scenario),
+                Err(error) => warn!("{}, generating bodies instead", error),
+            }
+        }
+        if let Some(two_body) = self.config.two_body {
+            return self.spawn_two_body(two_body);
+        }
+
+        let mut rng = seeded_rng(self.config.seed);
+        let sun_position = screen_center();
+        let sun_dimensions = Dimensions::from_mass(SUN_SIZE, self.config.radius_model);
+        let initial_velocity = self.config.initial_velocity;
+        // everything placed so far, so new bodies don't start out overlapping
+        let mut placed = SpatialGrid::new(self.config.broadphase_cell_size);
+        let mut placed_shapes = HashMap::new();
+        if !self.config.no_sun {
+            self.spawn_sun(sun_position, SUN_SIZE, None);
+            placed.insert(-1, sun_position, sun_dimensions.radius);
+            placed_shapes.insert(-1, (sun_position, sun_dimensions.radius));
+        }
+        let heaviest = self
+            .config
+            .mass_spectrum
+            .as_ref()
+            .map_or(BODY_INITIAL_MASS_MAX, MassSpectrum::heaviest);
+        let largest_radius = sun_dimensions
+            .radius
+            .max(Dimensions::from_mass(heaviest, self.config.radius_model).radius);
+
+        let bodies = (0..self.config.num_bodies)
+            .filter_map(|i| {
+                let mass = match &self.config.mass_spectrum {
+                    Some(spectrum) => spectrum.mass_at(rng.gen()),
+                    None => rng.gen_range(1., BODY_INITIAL_MASS_MAX),
+                };
+                let dimensions = Dimensions::from_mass(mass, self.config.radius_model);
+
+                // bodies that can't find a free spot are left out
+                let position = (0..SPAWN_ATTEMPTS)
+                    .map(|_| {
+                        Point2::new(
+                            rng.gen_range(0., WIDTH as f64),
+                            rng.gen_range(0., HEIGHT as f64),
+                        )
+                    })
+                    .find(|position| {
+                        placed
+                            .nearby(*position, dimensions.radius + largest_radius)
+                            .iter()
+                            .all(|id| {
+                                let (other_position, other_radius) = placed_shapes[id];
+                                !are_colliding(
+                                    *position,
+                                    dimensions.radius,
+                                    other_position,
+                                    other_radius,
+                                    0.,
+                                )
+                            })
+                    })?;
+                placed.insert(i, position, dimensions.radius);
+                placed_shapes.insert(i, (position, dimensions.radius));
+
+                let velocity = match initial_velocity {
+                    InitialVelocity::Random { speed } if speed > 0. => {
+                        Vector2::new(rng.gen_range(-speed, speed), rng.gen_range(-speed, speed))
+                    }
+                    InitialVelocity::Random { .. } => Vector2::new(0., 0.),
+                    // nothing to orbit
+                    InitialVelocity::Orbital { .. } if self.config.no_sun => Vector2::zeros(),
+                    InitialVelocity::Orbital { perturbation } => {
+                        let velocity = circular_orbit_velocity(
+                            &position,
+                            mass,
+                            &sun_position,
+                            sun_dimensions.mass,
+                        );
+                        let max_nudge = velocity.magnitude() * perturbation;
+                        if max_nudge > 0. {
+                            velocity
+                                + Vector2::new(
+                                    rng.gen_range(-max_nudge, max_nudge),
+                                    rng.gen_range(-max_nudge, max_nudge),
+                                )
+                        } else {
+                            velocity
+                        }
+                    }
+                };
+                let velocity = match &self.config.velocity_bias {
+                    Some(bias) => {
+                        let offset = position - sun_position;
+                        let (x, y) = bias.at(offset.x, offset.y);
+                        velocity + Vector2::new(x, y)
+                    }
+                    None => velocity,
+                };
+                Some(BodySpec {
+                    position,
+                    velocity,
+                    mass,
+                    density: 1.,
+                    name: None,
+                    color: None,
+                    collision_mask: CollisionMask::default(),
+                    restitution: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        for body in bodies {
+            self.spawn_body(body);
+        }
+    }
+
+    /// spawns exactly what the scenario lists, instead of generating bodies
+    pub(crate) fn load_scenario(&mut self, scenario: &Scenario) {
+        if let Some(sun) = &scenario.sun {
+            self.spawn_sun(Point2::from(sun.position), sun.mass, sun.color);
+        }
+        for spec in scenario_bodies(scenario) {
+            self.spawn_body(spec);
+        }
+    }
+
+    /// adds the bodies of the scenario at `path` to the world as it is, they get fresh ids,
+    /// the scenario's sun is only spawned when there's no sun yet
+    pub(crate) fn load_additive(&mut self, path: &Path) -> Result<(), String> {
+        let scenario = Scenario::load(path)?;
+        let has_sun = get_bodies(&self.world).iter().any(|body| body.sun);
+        match &scenario.sun {
+            Some(sun) if !has_sun => {
+                self.spawn_sun(Point2::from(sun.position), sun.mass, sun.color)
+            }
+            _ => {}
+        }
+        // a big scenario can trickle in under the spawn cap
+        for spec in scenario_bodies(&scenario) {
+            self.queue_spawn(spec);
+        }
+        Ok(())
+    }
+
+    /// writes out the end of the trajectory archive, if there is one, nothing is recorded after this
+    pub(crate) fn finish_recording(&mut self) -> Result<(), String> {
+        self.trajectory_archive
+            .take()
+            .map_or(Ok(()), TrajectoryArchive::finish)
+    }
+
+    /// writes every body out as a scenario, so `--scenario` can pick up where this left off
+    /// ids, selection and pinning aren't kept
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        let query = <(
+            Read<Data>,
+            Read<Position>,
+            Read<Velocity>,
+            Read<Dimensions>,
+            Read<Id>,
+        )>::query();
+        let mut bodies = query
+            .iter_entities(&self.world)
+            .map(|(entity, (data, position, velocity, dimensions, id))| {
+                let collision_mask = self
+                    .world
+                    .get_component::<CollisionMask>(entity)
+                    .map(|mask| *mask)
+                    .unwrap_or_default();
+                (
+                    id.id,
+                    data.sun,
+                    ScenarioBody {
+                        name: Some(data.name.clone()),
+                        position: [position.point.x, position.point.y],
+                        velocity: [velocity.vector.x, velocity.vector.y],
+                        mass: dimensions.mass,
+                        density: dimensions.density(self.config.radius_model),
+                        // ids aren't kept, so the color is
+                        color: self.body_color(id.id, &data),
+                        categories: collision_mask.categories,
+                        collides_with: collision_mask.collides_with,
+                        restitution: self
+                            .world
+                            .get_component::<Restitution>(entity)
+                            .map(|restitution| restitution.0),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        // loading hands out ids in file order, so the saved order is the order they'll come back in
+        bodies.sort_by_key(|(id, _, _)| *id);
+        let (suns, bodies): (Vec<_>, Vec<_>) = bodies.into_iter().partition(|(_, sun, _)| *sun);
+        Scenario {
+            sun: suns.into_iter().next().map(|(_, _, sun)| ScenarioSun {
+                position: sun.position,
+                mass: sun.mass,
+                color: sun.color,
+            }),
+            bodies: bodies.into_iter().map(|(_, _, body)| body).collect(),
+        }
+        .save(path)
+    }
+
+    fn spawn_sun(&mut self, position: Point2<f64>, mass: f64, color: Option<[u8; 3]>) {
         self.world.insert(
             (),
             vec![(
                 Data {
                     name: "sun".to_string(),
                     sun: true,
+                    color,
                 },
-                Position {
-                    point: Point2::new((WIDTH as f32 / 2.).into(), (HEIGHT as f32 / 2.).into()),
-                },
+                Position { point: position },
                 Velocity {
                     vector: Vector2::new(0., 0.),
                 },
-                Dimensions::from_mass(SUN_SIZE),
+                Dimensions::from_mass(mass, self.config.radius_model),
                 MetaInfo::default(),
                 Id { id: -1 },
             )],
         );
-        self.world.insert(
-            (),
-            (0..NUM_BODIES).map(|i| {
-                let x = rng.gen_range(0., WIDTH as f64);
-                let y = rng.gen_range(0., HEIGHT as f64);
+    }
 
-                let x_velocity = match INITIAL_SPEED {
-                    0 => 0.,
-                    speed => rng.gen_range(-speed as f64, speed as f64),
-                };
-                let y_velocity = match INITIAL_SPEED {
-                    0 => 0.,
-                    speed => rng.gen_range(-speed as f64, speed as f64),
-                };
+    /// the color the body was given, or the one worked out from its id when that's turned on
+    fn body_color(&self, id: i32, data: &Data) -> Option<[u8; 3]> {
+        match data.color {
+            None if self.config.id_colors && !data.sun => Some(id_color(id)),
+            color => color,
+        }
+    }
 
-                let mass = rng.gen_range(1., BODY_INITIAL_MASS_MAX);
-                (
-                    Data {
-                        name: i.to_string(),
-                        sun: false,
-                    },
-                    Position {
-                        point: Point2::new(x, y),
-                    },
-                    Velocity {
-                        vector: Vector2::new(x_velocity, y_velocity),
-                    },
-                    Dimensions::from_mass(mass),
-                    MetaInfo::default(),
-                    Id { id: i },
-                )
-            }),
+    /// the sun with a single satellite at the apoapsis of the requested ellipse
+    fn spawn_two_body(&mut self, two_body: TwoBody) {
+        let sun_position = screen_center();
+        self.spawn_sun(sun_position, SUN_SIZE, None);
+        let (offset, velocity) = state_at_apoapsis(
+            two_body.semi_major_axis,
+            two_body.eccentricity,
+            // gravity isn't divided by the orbiting body's mass, so it's part of the pull
+            GRAVITATIONAL_CONSTANT * SATELLITE_MASS * SUN_SIZE,
         );
+        self.spawn_body(BodySpec {
+            position: sun_position + offset,
+            velocity,
+            mass: SATELLITE_MASS,
+            density: 1.,
+            name: None,
+            color: None,
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        });
     }
 
-    pub(crate) fn tick(&mut self, dt: f64, camera_x_axis: f64, camera_y_axis: f64) {
-        if self.paused {
-            if self.predicted_orbit.is_none() {
-                self.predicted_orbit = Some(predict_orbit(dt, &self.world));
+    /// adds a body and returns its id, which stays the same for as long as the body is around
+    pub(crate) fn spawn_body(&mut self, spec: BodySpec) -> i32 {
+        self.spawn_body_entity(spec).0
+    }
+
+    fn spawn_body_entity(&mut self, spec: BodySpec) -> (i32, Entity) {
+        let id = self.ids.next();
+        let entity = self.world.insert(
+            (),
+            vec![(
+                Data {
+                    name: spec.name.unwrap_or_else(|| id.to_string()),
+                    sun: false,
+                    color: spec.color,
+                },
+                Position {
+                    point: spec.position,
+                },
+                Velocity {
+                    vector: spec.velocity,
+                },
+                Dimensions::from_mass_and_density(
+                    spec.mass,
+                    spec.density,
+                    self.config.radius_model,
+                ),
+                MetaInfo::default(),
+                Id { id },
+            )],
+        )[0];
+        self.world
+            .add_component(entity, SpawnTick(self.tick_count.0))
+            .expect("body was just spawned");
+        if spec.collision_mask != CollisionMask::default() {
+            self.world
+                .add_component(entity, spec.collision_mask)
+                .expect("body was just spawned");
+        }
+        if let Some(restitution) = spec.restitution {
+            self.world
+                .add_component(entity, Restitution(restitution))
+                .expect("body was just spawned");
+        }
+        self.prediction = None;
+        (id, entity)
+    }
+
+    /// adds a body straight away, or with a spawn cap, at the end of the tick it's its turn in
+    fn queue_spawn(&mut self, spec: BodySpec) {
+        match self.config.max_spawns_per_tick {
+            Some(_) => self.spawn_queue.push_back(PendingSpawn::Body(spec)),
+            None => {
+                self.spawn_body(spec);
+            }
+        }
+    }
+
+    /// spawns as much of the queue as the cap allows, oldest first
+    fn spawn_queued(&mut self) {
+        let count = self
+            .config
+            .max_spawns_per_tick
+            .map_or(usize::MAX, NonZeroUsize::get)
+            .min(self.spawn_queue.len());
+        for pending in self.spawn_queue.drain(..count).collect::<Vec<_>>() {
+            match pending {
+                PendingSpawn::Body(spec) => {
+                    self.spawn_body(spec);
+                }
+                PendingSpawn::Comet(lifetime) => self.spawn_comet(lifetime),
+            }
+        }
+    }
+
+    /// sends a comet in from the far end of a long thin orbit, from a random direction around the sun
+    fn spawn_comet(&mut self, lifetime: CometLifetime) {
+        let sun = match get_bodies(&self.world).into_iter().find(|body| body.sun) {
+            Some(sun) => sun,
+            None => return,
+        };
+        let angle = self.comet_rng.gen_range(0., 2. * PI);
+        let outward = Vector2::new(angle.cos(), angle.sin());
+        // vis-viva at apoapsis
+        let mu = GRAVITATIONAL_CONSTANT * COMET_MASS * sun.mass;
+        let speed = (mu * (1. - COMET_ECCENTRICITY) / COMET_APOAPSIS).sqrt();
+        let (_, entity) = self.spawn_body_entity(BodySpec {
+            position: sun.position + outward * COMET_APOAPSIS,
+            velocity: sun.velocity + Vector2::new(-outward.y, outward.x) * speed,
+            mass: COMET_MASS,
+            density: 1.,
+            name: None,
+            color: None,
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        });
+        let lifetime = match lifetime {
+            CometLifetime::Ticks(ticks) => Lifetime::UntilTick(self.tick_count.0 + ticks),
+            CometLifetime::Flyby => Lifetime::Flyby { falling: false },
+        };
+        self.world
+            .add_component(entity, lifetime)
+            .expect("comet was just spawned");
+    }
+
+    /// removes the bodies whose lifetime is up
+    fn expire(&mut self) {
+        let sun = get_bodies(&self.world).into_iter().find(|body| body.sun);
+        let tick = self.tick_count.0;
+        let mut expired = vec![];
+        for (entity, (position, velocity, mut lifetime)) in
+            <(Read<Position>, Read<Velocity>, Write<Lifetime>)>::query()
+                .iter_entities_mut(&mut self.world)
+        {
+            let done = match (*lifetime, &sun) {
+                (Lifetime::UntilTick(last), _) => tick >= last,
+                (Lifetime::Flyby { falling }, Some(sun)) => {
+                    let closing =
+                        (position.point - sun.position).dot(&(velocity.vector - sun.velocity)) < 0.;
+                    *lifetime = Lifetime::Flyby {
+                        falling: falling || closing,
+                    };
+                    falling && !closing
+                }
+                // nothing to fly by
+                (Lifetime::Flyby { .. }, None) => false,
+            };
+            if done {
+                expired.push(entity);
             }
+        }
+        for entity in expired {
+            self.world.delete(entity);
+        }
+    }
+
+    /// current state of a body, None once it's been swallowed or deleted
+    /// tests check on bodies by id with it, the app itself never reads bodies back
+    #[cfg(test)]
+    pub(crate) fn body(&self, id: i32) -> Option<BodyState> {
+        <(
+            Read<Id>,
+            Read<Data>,
+            Read<Position>,
+            Read<Velocity>,
+            Read<Dimensions>,
+        )>::query()
+        .iter(&self.world)
+        .find(|(body_id, _, _, _, _)| body_id.id == id)
+        .map(|(_, data, position, velocity, dimensions)| BodyState {
+            name: data.name.clone(),
+            position: position.point,
+            velocity: velocity.vector,
+            mass: dimensions.mass,
+            radius: dimensions.radius,
+        })
+    }
+
+    pub(crate) fn tick(&mut self, dt: f64) {
+        if self.paused {
+            // the budget is never set on the web, which has no clock to check it against
+            let deadline = self
+                .config
+                .prediction_budget
+                .map(|millis| Instant::now() + Duration::from_secs_f64(millis / 1000.));
+            let (world, config) = (&self.world, &self.config);
+            self.prediction
+                .get_or_insert_with(|| Prediction::new(dt, world, config))
+                .extend(&self.config, deadline);
+            // nothing moves, so there's nothing to draw in between
+            self.previous_positions.clear();
             return;
         }
+        let gravitational_constant = self.gravitational_constant();
+        self.tick_count.0 += 1;
+        self.impacts.clear();
 
-        let bodies = get_bodies(&self.world);
+        let substeps = self.config.substeps.max(1);
+        let mut bodies = get_bodies(&self.world);
+        self.previous_positions = bodies.iter().map(|body| (body.id, body.position)).collect();
+        let suns = bodies
+            .iter()
+            .filter(|body| body.sun)
+            .map(|body| body.id)
+            .collect::<Vec<_>>();
+        let mut deleted_bodies = HashMap::new();
+        let mut impacts = vec![];
+        // the merge cap is per tick, so later substeps get what earlier ones left over
+        let mut step_config = self.config.clone();
+        for _ in 0..substeps {
+            #[cfg(debug_assertions)]
+            let bodies_before = bodies.clone();
 
-        let updated_bodies = do_one_physics_step(dt, bodies);
+            // a diagnostic, to see how much the outcome depends on the order bodies are processed in
+            if self.config.shuffle_update_order {
+                bodies.shuffle(&mut self.shuffle_rng);
+            }
+            // applied here rather than in the physics step, so it stays out of orbit predictions
+            if let Some(cursor) = self.attractor {
+                for body in bodies.iter_mut().filter(|body| !body.sun && !body.frozen) {
+                    body.velocity +=
+                        cursor_acceleration(&self.config.cursor_attractor, &body.position, &cursor)
+                            * (dt / substeps as f64);
+                }
+            }
+            let bodies_before_step = if self.collision_breakpoint {
+                bodies.clone()
+            } else {
+                vec![]
+            };
+            let (updated_bodies, contacts) = physics_step(
+                dt / substeps as f64,
+                bodies,
+                &step_config,
+                gravitational_constant,
+            );
+            if self.collision_breakpoint {
+                let selected = bodies_before_step
+                    .iter()
+                    .filter(|body| body.selected)
+                    .map(|body| body.id)
+                    .collect::<Vec<_>>();
+                let hit = contacts.iter().any(|contact| {
+                    let (left, right) = contact.pair;
+                    selected.contains(&left) || selected.contains(&right)
+                });
+                if hit {
+                    // the whole tick is thrown away, so the world stays as it was right before
+                    self.collision_breakpoint = false;
+                    self.paused = true;
+                    self.tick_count.0 -= 1;
+                    self.previous_positions.clear();
+                    self.impacts = contacts;
+                    return;
+                }
+            }
+            self.impacts.extend(contacts);
+            #[cfg(debug_assertions)]
+            assert_mass_conserved(&bodies_before, &updated_bodies);
 
-        let (bodies_to_delete, bodies_to_update): (Vec<_>, Vec<_>) =
-            updated_bodies.into_iter().partition(|body| body.delete);
-        let bodies_to_update = bodies_to_update
+            if self.config.shockwaves {
+                let velocities = updated_bodies
+                    .iter()
+                    .map(|body| (body.id, body.velocity))
+                    .collect::<HashMap<_, _>>();
+                impacts.extend(updated_bodies.iter().filter(|body| body.delete).filter_map(
+                    |body| {
+                        let survivor_velocity = velocities.get(&body.absorbed_by?)?;
+                        Some((
+                            body.position,
+                            impact_energy(body.mass, body.velocity, *survivor_velocity),
+                        ))
+                    },
+                ));
+            }
+            let (bodies_to_delete, bodies_to_keep): (Vec<_>, Vec<_>) =
+                updated_bodies.into_iter().partition(|body| body.delete);
+            if let Some(max_merges) = &mut step_config.max_merges_per_tick {
+                *max_merges = max_merges.saturating_sub(bodies_to_delete.len());
+            }
+            deleted_bodies.extend(bodies_to_delete.into_iter().map(|body| (body.id, body)));
+            bodies = bodies_to_keep;
+        }
+        self.accreted_mass += deleted_bodies
+            .values()
+            .filter(|body| body.absorbed_by.is_some_and(|id| suns.contains(&id)))
+            .map(|body| body.mass)
+            .sum::<f64>();
+        let settled = self.reached_equilibrium(&bodies, !deleted_bodies.is_empty());
+        if settled {
+            self.paused = true;
+        }
+        let recorded_tick = self.ticks_before_reset + self.tick_count.0;
+        if let Some(event_log) = &mut self.event_log {
+            for impact in &self.impacts {
+                let event = SimulationEvent::Impact {
+                    left: impact.pair.0,
+                    right: impact.pair.1,
+                    point: [impact.point.x, impact.point.y],
+                    speed: impact.speed,
+                };
+                event_log.record(recorded_tick, event);
+            }
+            let mut swallowed = deleted_bodies.values().collect::<Vec<_>>();
+            swallowed.sort_by_key(|body| body.id);
+            for body in swallowed {
+                let event = match body.absorbed_by {
+                    Some(survivor) if suns.contains(&survivor) => {
+                        SimulationEvent::Accretion { swallowed: body.id }
+                    }
+                    Some(survivor) => SimulationEvent::Merge {
+                        survivor,
+                        swallowed: body.id,
+                    },
+                    None => continue,
+                };
+                event_log.record(recorded_tick, event);
+            }
+            if settled {
+                event_log.record(recorded_tick, SimulationEvent::EquilibriumReached);
+            }
+            if let Err(error) = event_log.flush() {
+                error!("{}", error);
+            }
+        }
+        let bodies_to_update = bodies
             .into_iter()
             .map(|body| (body.id, body))
             .collect::<HashMap<_, _>>();
 
-        let ids_to_delete = bodies_to_delete
-            .into_iter()
-            .map(|body| body.id)
-            .collect::<Vec<_>>();
+        for shockwave in &mut self.shockwaves {
+            shockwave.age += 1;
+        }
+        self.shockwaves
+            .retain(|shockwave| shockwave.age < SHOCKWAVE_TICKS);
+        self.shockwaves
+            .extend(impacts.into_iter().map(|(position, energy)| Shockwave {
+                position,
+                radius: shockwave_radius(energy),
+                age: 0,
+            }));
+        // follows who swallowed who until it finds a body that's still around
+        let survivor_of = |mut id: i32| loop {
+            if let Some(survivor) = bodies_to_update.get(&id) {
+                return Some(survivor);
+            }
+            id = deleted_bodies.get(&id)?.absorbed_by?;
+        };
+
         let mut entities_to_delete = vec![];
 
         let query = <(
@@ -173,290 +908,4990 @@ impl Core {
         for (entity, (mut pos, mut velocity, mut dimensions, id)) in
             query.iter_entities_mut(&mut self.world)
         {
-            if ids_to_delete.contains(&id.id) {
+            if deleted_bodies.contains_key(&id.id) {
                 entities_to_delete.push(entity)
             } else {
                 let updated_version = bodies_to_update
                     .get(&id.id)
                     .expect("updated body should exist");
                 pos.point = updated_version.position;
-                // camera movement
-                pos.point += Vector2::new(camera_x_axis, camera_y_axis);
                 velocity.vector = updated_version.velocity;
-                dimensions.mass = updated_version.mass; //todo recalculate radius
+                dimensions.mass = updated_version.mass;
+                dimensions.radius = updated_version.radius;
             }
         }
 
         for entity in entities_to_delete {
             self.world.delete(entity);
         }
+
+        let mut orphaned_debris = vec![];
+        for (entity, (mut position, mut debris)) in
+            <(Write<Position>, Write<Debris>)>::query().iter_entities_mut(&mut self.world)
+        {
+            match survivor_of(debris.parent) {
+                Some(parent) => {
+                    if parent.id != debris.parent {
+                        // the parent got swallowed, stay put and stick to whoever swallowed it
+                        debris.parent = parent.id;
+                        debris.offset = position.point - parent.position;
+                    }
+                    position.point = parent.position + debris.offset;
+                }
+                None => orphaned_debris.push(entity),
+            }
+        }
+        for entity in orphaned_debris {
+            self.world.delete(entity);
+        }
+        if self.config.debris {
+            let new_debris = deleted_bodies
+                .values()
+                .filter(|body| !body.sun)
+                .filter_map(|body| {
+                    let parent = survivor_of(body.absorbed_by?)?;
+                    Some((
+                        Position {
+                            point: body.position,
+                        },
+                        Debris {
+                            parent: parent.id,
+                            offset: body.position - parent.position,
+                        },
+                    ))
+                })
+                .collect::<Vec<_>>();
+            self.world.insert((), new_debris);
+        }
+
+        self.expire();
+        if let Some(comets) = self.config.comets {
+            if self.tick_count.0.is_multiple_of(comets.interval.get()) {
+                self.spawn_queue
+                    .push_back(PendingSpawn::Comet(comets.lifetime));
+            }
+        }
+        self.spawn_queued();
+
+        let bodies = get_bodies(&self.world);
+        self.broadphase = build_broadphase(
+            self.config.broadphase_cell_size,
+            self.config.contact_margin,
+            &bodies,
+        );
+
+        if let Some(length) = self.config.trail_length {
+            record_trails(&mut self.trails, &bodies, length);
+        }
+        if self.trajectory_archive.is_some() {
+            let states = self
+                .iter_bodies()
+                .sorted_by_key(|body| body.id)
+                .collect::<Vec<_>>();
+            let tick = self.ticks_before_reset + self.tick_count.0;
+            if let Some(Err(error)) = self
+                .trajectory_archive
+                .as_mut()
+                .map(|archive| archive.record(tick, &states))
+            {
+                error!("{}", error);
+            }
+        }
+
+        if self.tick_count.0.is_multiple_of(GRAPH_SAMPLE_INTERVAL) {
+            self.largest_mass_history
+                .push(self.tick_count.0, largest_mass(&bodies));
+            if let Some((first, second)) = two_heaviest(&bodies) {
+                self.separation_history.push(
+                    self.tick_count.0,
+                    (first.position - second.position).magnitude(),
+                );
+            }
+        }
+    }
+
+    pub(crate) fn draw(&self) -> Frame {
+        self.draw_interpolated(1.)
     }
 
-    pub(crate) fn draw(&self) -> (Vec<Drawable>, Vec<Point2<f64>>) {
-        let query = <(Read<Position>, Read<Data>, Read<Dimensions>)>::query();
+    /// like `draw`, but with bodies `overstep_fraction` of the way from where they were before
+    /// the last tick to where they are now, when interpolation is turned on
+    pub(crate) fn draw_interpolated(&self, overstep_fraction: f64) -> Frame {
+        let fraction = if self.config.interpolate {
+            overstep_fraction.clamp(0., 1.)
+        } else {
+            1.
+        };
+        let scrubbed = self.scrubbed();
+        // how far back each body is drawn from where it actually is,
+        // or how far ahead the prediction has it while inspecting
+        let shifts = get_bodies(&self.world)
+            .into_iter()
+            .filter_map(|body| {
+                let drawn_at = match scrubbed {
+                    Some(snapshot) => *snapshot.get(&body.id)?,
+                    None => interpolate(
+                        *self.previous_positions.get(&body.id)?,
+                        body.position,
+                        fraction,
+                    ),
+                };
+                Some((body.id, drawn_at - body.position))
+            })
+            .collect::<HashMap<_, _>>();
+        // swallowed along the way, or left out of the prediction
+        let hidden = |id: i32| scrubbed.is_some_and(|snapshot| !snapshot.contains_key(&id));
+        let shift = |id: i32| shifts.get(&id).copied().unwrap_or_else(Vector2::zeros);
+
+        let sun = get_bodies(&self.world).into_iter().find(|body| body.sun);
+        let sun_position = sun.as_ref().map(|sun| sun.position);
+        let query = <(
+            Read<Position>,
+            Read<Velocity>,
+            Read<Data>,
+            Read<Dimensions>,
+            Read<Id>,
+        )>::query();
         let mut bodies = query
-            .iter(&self.world)
-            .map(|(pos, data, dimensions)| {
+            .iter_entities(&self.world)
+            .filter(|(_, (_, _, _, _, id))| !hidden(id.id))
+            .map(|(entity, (pos, velocity, data, dimensions, id))| {
                 let position = *pos;
                 let position: Point2<f64> = position.point;
+                let escaping = match (&sun, self.config.escape_color) {
+                    (Some(sun), Some(_)) if !data.sun => is_unbound(
+                        position - sun.position,
+                        velocity.vector - sun.velocity,
+                        GRAVITATIONAL_CONSTANT * dimensions.mass * sun.mass,
+                    ),
+                    _ => false,
+                };
                 Drawable {
-                    position,
+                    position: position + shift(id.id),
                     sun: data.sun,
                     radius: dimensions.radius,
                     select_marker: false,
+                    label: if self.config.labels {
+                        Some(data.name.clone())
+                    } else {
+                        None
+                    },
+                    debris: false,
+                    color: match (self.config.escape_color, self.config.sun_heating) {
+                        (Some(Rgb(color)), _) if escaping => Some(color),
+                        (_, Some(heating)) if data.sun => Some(heated_sun_color(
+                            data.color.unwrap_or(SUN_COLOR),
+                            &heating,
+                            self.accreted_mass,
+                        )),
+                        _ => match (&self.config.age_colors, data.sun) {
+                            (Some(colors), false) => Some(age_color(
+                                colors,
+                                self.world
+                                    .get_component::<SpawnTick>(entity)
+                                    .map_or(0, |spawned| spawned.0),
+                                self.tick_count.0,
+                            )),
+                            _ => self.body_color(id.id, &data),
+                        },
+                    },
+                    illumination: match (self.config.sun_luminosity, sun_position) {
+                        (Some(luminosity), Some(sun_position)) if !data.sun => Some(illumination(
+                            luminosity,
+                            (position - sun_position).magnitude(),
+                        )),
+                        _ => None,
+                    },
                 }
             })
             .collect::<Vec<_>>();
 
-        let query = <(Read<Position>, Read<Dimensions>, Read<MetaInfo>)>::query();
+        let query = <(Read<Position>, Read<Dimensions>, Read<MetaInfo>, Read<Id>)>::query();
         let mut selection_markers = query
             .iter(&self.world)
-            .filter(|(_, _, meta_info)| meta_info.selected)
-            .map(|(position, dimensions, _)| Drawable {
-                position: position.point,
+            .filter(|(_, _, meta_info, id)| meta_info.selected && !hidden(id.id))
+            .map(|(position, dimensions, _, id)| Drawable {
+                position: position.point + shift(id.id),
                 sun: false,
                 radius: dimensions.radius,
                 select_marker: true,
+                label: None,
+                debris: false,
+                color: None,
+                illumination: None,
             })
             .collect::<Vec<_>>();
 
         bodies.append(&mut selection_markers);
-        (bodies, self.predicted_orbit.clone().unwrap_or_default())
-    }
-
-    pub(crate) fn click(&mut self, click_position: Vector2<f64>) {
-        self.predicted_orbit = None;
-        let id_of_clicked_body = {
-            <(Read<Position>, Read<Dimensions>, Read<Id>)>::query()
-                .iter(&self.world)
-                .map(|(position, dimensions, id)| {
-                    let ball = Ball::new(dimensions.radius);
-                    let distance = ball.distance_to_point(
-                        &Isometry2::translation(position.point.x, position.point.y),
-                        &Point {
-                            coords: click_position,
-                        },
-                        true,
-                    );
-                    (distance, id)
-                })
-                .filter(|(distance, _)| distance < &5f64)
-                .sorted_by(|(left_distance, _), (right_distance, _)| {
-                    left_distance
-                        .partial_cmp(right_distance)
-                        .expect("couldn't unwrap ordering")
+
+        let mut debris = <(Read<Position>, Read<Debris>)>::query()
+            .iter(&self.world)
+            .map(|(position, debris)| Drawable {
+                position: position.point + shift(debris.parent),
+                sun: false,
+                radius: DEBRIS_RADIUS,
+                select_marker: false,
+                label: None,
+                debris: true,
+                color: None,
+                illumination: None,
+            })
+            .collect::<Vec<_>>();
+        bodies.append(&mut debris);
+
+        let grid_cells = if self.show_broadphase {
+            let cell_size = self.broadphase.cell_size();
+            self.broadphase
+                .occupancy()
+                .into_iter()
+                .map(|((x, y), occupancy)| GridCell {
+                    corner: Point2::new(x as f64 * cell_size, y as f64 * cell_size),
+                    size: cell_size,
+                    occupancy,
                 })
-                .next()
-                .map(|(_, id)| Id { id: id.id })
+                .collect()
+        } else {
+            vec![]
         };
 
-        if let Some(clicked_id) = id_of_clicked_body {
-            // we clicked something, clear selected
-            <(Read<Id>, Write<MetaInfo>)>::query().for_each_mut(
-                &mut self.world,
-                |(id, mut meta_info)| {
-                    if &clicked_id == id.as_ref() {
-                        meta_info.selected = true;
-                    } else {
-                        meta_info.selected = false;
-                    }
-                },
-            );
+        let selected = get_bodies(&self.world)
+            .into_iter()
+            .filter(|body| body.selected)
+            .collect::<Vec<_>>();
+        let orbit = match (selected.as_slice(), &sun) {
+            ([body], Some(sun)) if !body.sun => orbital_elements(
+                body.position - sun.position,
+                body.velocity - sun.velocity,
+                // gravity isn't divided by the orbiting body's mass, so it's part of the pull
+                GRAVITATIONAL_CONSTANT * body.mass * sun.mass,
+            ),
+            _ => None,
+        };
+        let group = if selected.len() > 1 {
+            Some(diagnostics(&selected, self.config.diagnostic_threads))
         } else {
-            <Write<MetaInfo>>::query().for_each_mut(&mut self.world, |mut meta_info| {
-                meta_info.selected = false;
-            });
-        }
-    }
-
+            None
+        };
+        let corona = match (self.config.corona, &sun) {
+            (Some(multiple), Some(sun)) => Some(Corona {
+                position: sun.position + shift(sun.id),
+                radius: corona_radius(sun.radius, multiple, self.tick_count),
+            }),
+            _ => None,
+        };
+        let hill_sphere = match (self.config.hill_sphere, &sun) {
+            (Some(sphere), Some(sun)) => Some(Outline {
+                position: sun.position + shift(sun.id),
+                radius: hill_radius(sun.mass, sphere.primary_mass, sphere.distance),
+            }),
+            _ => None,
+        };
+
+        Frame {
+            drawables: bodies,
+            shockwaves: self
+                .shockwaves
+                .iter()
+                .map(|shockwave| {
+                    let progress = shockwave.age as f64 / SHOCKWAVE_TICKS as f64;
+                    Ring {
+                        position: shockwave.position,
+                        radius: shockwave.radius * (1. + SHOCKWAVE_GROWTH * progress),
+                        opacity: 1. - progress,
+                    }
+                })
+                .collect(),
+            corona,
+            hill_sphere,
+            group,
+            orbit,
+            largest_mass_history: if self.show_mass_graph {
+                self.largest_mass_history.samples()
+            } else {
+                vec![]
+            },
+            separation_history: if self.show_separation_graph {
+                self.separation_history.samples()
+            } else {
+                vec![]
+            },
+            predicted_orbit: self
+                .prediction
+                .as_ref()
+                .map(|prediction| prediction.path.clone())
+                .unwrap_or_default(),
+            time_to_impact: self
+                .prediction
+                .as_ref()
+                .and_then(|prediction| prediction.time_to_impact()),
+            grid_cells,
+            measurement: self.measurement(),
+            trails: self
+                .trails
+                .values()
+                .map(|trail| trail.iter().copied().collect())
+                .collect(),
+            scrubber: self.scrubber(),
+        }
+        .offset_by(self.view_offset())
+    }
+
+    /// None until both ends have been clicked, or when a measured body is gone
+    fn measurement(&self) -> Option<Measurement> {
+        let end = |endpoint: &Endpoint| match endpoint {
+            Endpoint::Point(point) => Some((*point, None)),
+            Endpoint::Body(id) => get_bodies(&self.world)
+                .into_iter()
+                .find(|body| body.id == *id)
+                .map(|body| (body.position, Some(body.velocity))),
+        };
+        match self.measurement.as_slice() {
+            [from, to] => {
+                let (from, from_velocity) = end(from)?;
+                let (to, to_velocity) = end(to)?;
+                Some(Measurement {
+                    from,
+                    to,
+                    distance: (to - from).magnitude(),
+                    motion: from_velocity
+                        .zip(to_velocity)
+                        .map(|(from_velocity, to_velocity)| {
+                            relative_motion(&from, &from_velocity, &to, &to_velocity)
+                        }),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// how far everything is moved when drawn, to keep the frame of reference in the middle
+    fn view_offset(&self) -> Vector2<f64> {
+        let bodies = get_bodies(&self.world);
+        let reference = match self.frame_of_reference {
+            FrameOfReference::Screen => None,
+            FrameOfReference::Sun => bodies.iter().find(|body| body.sun).map(|sun| sun.position),
+            FrameOfReference::Barycenter if bodies.is_empty() => None,
+            FrameOfReference::Barycenter => {
+                Some(barycenter(&bodies, self.config.diagnostic_threads))
+            }
+        };
+        reference
+            .map(|reference| screen_center() - reference)
+            .unwrap_or_else(Vector2::zeros)
+            + self.camera
+    }
+
+    /// moves the view, the bodies stay where they are
+    pub(crate) fn pan(&mut self, offset: Vector2<f64>) {
+        self.camera += offset;
+    }
+
+    /// the body under `position`, or the closest one whose edge is within the pick tolerance of it,
+    /// which is in pixels so it's divided by `zoom`
+    fn body_at(&self, position: Vector2<f64>, zoom: f64) -> Option<i32> {
+        <(Read<Position>, Read<Dimensions>, Read<Id>)>::query()
+            .iter(&self.world)
+            .map(|(body_position, dimensions, id)| {
+                let ball = Ball::new(dimensions.radius);
+                let distance = ball.distance_to_point(
+                    &Isometry2::translation(body_position.point.x, body_position.point.y),
+                    &Point { coords: position },
+                    true,
+                );
+                (distance, id.id)
+            })
+            .filter(|(distance, _)| *distance < self.config.pick_tolerance / zoom)
+            .sorted_by(|(left_distance, _), (right_distance, _)| {
+                left_distance
+                    .partial_cmp(right_distance)
+                    .expect("couldn't unwrap ordering")
+            })
+            .next()
+            .map(|(_, id)| id)
+    }
+
+    /// adds an end to the measurement, the body under `click_position` if there is one and the
+    /// spot itself otherwise, a third click starts a new measurement
+    pub(crate) fn measure(&mut self, click_position: Vector2<f64>, zoom: f64) {
+        let position = click_position - self.view_offset();
+        let end = match self.body_at(position, zoom) {
+            Some(id) => Endpoint::Body(id),
+            None => Endpoint::Point(Point2::from(position)),
+        };
+        if self.measurement.len() == 2 {
+            self.measurement.clear();
+        }
+        self.measurement.push(end);
+    }
+
+    pub(crate) fn clear_measurement(&mut self) {
+        self.measurement.clear();
+    }
+
+    /// switches between drawing the screen as is, centered on the sun and centered on the barycenter
+    pub(crate) fn cycle_frame_of_reference(&mut self) {
+        self.frame_of_reference = self.frame_of_reference.next();
+    }
+
+    /// selects the clicked body, or with `extend_selection` adds/removes it from the selection
+    pub(crate) fn click(
+        &mut self,
+        click_position: Vector2<f64>,
+        zoom: f64,
+        extend_selection: bool,
+    ) {
+        self.prediction = None;
+        let click_position = click_position - self.view_offset();
+        let id_of_clicked_body = self.body_at(click_position, zoom).map(|id| Id { id });
+
+        if extend_selection {
+            if let Some(clicked_id) = id_of_clicked_body {
+                <(Read<Id>, Write<MetaInfo>)>::query().for_each_mut(
+                    &mut self.world,
+                    |(id, mut meta_info)| {
+                        if &clicked_id == id.as_ref() {
+                            meta_info.selected = meta_info.selected.not();
+                        }
+                    },
+                );
+            }
+        } else if let Some(clicked_id) = id_of_clicked_body {
+            // we clicked something, clear selected
+            <(Read<Id>, Write<MetaInfo>)>::query().for_each_mut(
+                &mut self.world,
+                |(id, mut meta_info)| {
+                    if &clicked_id == id.as_ref() {
+                        meta_info.selected = true;
+                    } else {
+                        meta_info.selected = false;
+                    }
+                },
+            );
+        } else {
+            <Write<MetaInfo>>::query().for_each_mut(&mut self.world, |mut meta_info| {
+                meta_info.selected = false;
+            });
+        }
+    }
+
+    /// whether nothing has merged and the kinetic energy has held steady for the whole equilibrium window,
+    /// starts a new window when it has so unpausing doesn't stop again straight away
+    fn reached_equilibrium(&mut self, bodies: &[Body], merged: bool) -> bool {
+        let equilibrium = match self.config.equilibrium {
+            Some(equilibrium) => equilibrium,
+            None => return false,
+        };
+        if merged {
+            self.kinetic_energy_window.clear();
+        }
+        let window = &mut self.kinetic_energy_window;
+        window.push_back(kinetic_energy(bodies));
+        if (window.len() as u64) < equilibrium.ticks.get() {
+            return false;
+        }
+        while window.len() as u64 > equilibrium.ticks.get() {
+            window.pop_front();
+        }
+        let (min, max) = window
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &energy| {
+                (min.min(energy), max.max(energy))
+            });
+        let settled = max - min <= equilibrium.threshold * max.abs();
+        if settled {
+            window.clear();
+        }
+        settled
+    }
+
     pub(crate) fn pause(&mut self) {
         self.paused = self.paused.not();
+        self.scrub = None;
+    }
+
+    /// moves the inspected moment `samples` predicted samples ahead, or back for negative,
+    /// going back past the first one returns to the present
+    pub(crate) fn scrub(&mut self, samples: isize) {
+        let index = self.scrub.map_or(-1, |index| index as isize) + samples;
+        self.scrub_to(usize::try_from(index).ok());
+    }
+
+    /// shows the predicted snapshot at `index` instead of the world, only while paused in inspect mode,
+    /// past what's been predicted so far stops at the last snapshot
+    pub(crate) fn scrub_to(&mut self, index: Option<usize>) {
+        let predicted = self
+            .prediction
+            .as_ref()
+            .map_or(0, |prediction| prediction.snapshots.len());
+        self.scrub = match index {
+            Some(_) if !self.paused || !self.config.inspect || predicted == 0 => None,
+            index => index.map(|index| index.min(predicted - 1)),
+        };
+    }
+
+    fn scrubber(&self) -> Option<Scrubber> {
+        if !self.paused || !self.config.inspect {
+            return None;
+        }
+        let interval = self.config.prediction_sample_interval.get();
+        let (predicted, time_step) = self.prediction.as_ref().map_or((0, 0.), |prediction| {
+            (prediction.snapshots.len(), prediction.time_step)
+        });
+        let index = self.scrubbed().and(self.scrub);
+        Some(Scrubber {
+            index,
+            predicted,
+            total: self.config.prediction_steps.div_ceil(interval),
+            // a snapshot is taken after the step it's sampled at
+            seconds_ahead: index.map(|index| (index * interval + 1) as f64 * time_step),
+        })
+    }
+
+    /// the predicted snapshot being inspected, if any
+    fn scrubbed(&self) -> Option<&HashMap<i32, Point2<f64>>> {
+        let prediction = self.prediction.as_ref()?;
+        prediction.snapshots.get(self.scrub?)
+    }
+
+    /// removes the selected bodies, works while paused so the layout can be edited
+    pub(crate) fn delete_selected(&mut self) {
+        let selected = <Read<MetaInfo>>::query()
+            .iter_entities(&self.world)
+            .filter(|(_, meta_info)| meta_info.selected)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in selected {
+            self.world.delete(entity);
+        }
+        self.prediction = None;
+    }
+
+    /// pins the selected bodies in place, or releases them if they already are
+    pub(crate) fn toggle_static_selected(&mut self) {
+        let selected = <Read<MetaInfo>>::query()
+            .iter_entities(&self.world)
+            .filter(|(_, meta_info)| meta_info.selected)
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in selected {
+            let result = if self.world.get_component::<Static>(entity).is_some() {
+                self.world.remove_component::<Static>(entity)
+            } else {
+                self.world.add_component(entity, Static)
+            };
+            result.expect("selected body should exist");
+        }
+        self.prediction = None;
+    }
+
+    /// throws every body away and lays them out again from the config, as if the app had just started
+    /// only the overlay toggles survive, and the event log and trajectory archive carry on where they were
+    pub(crate) fn reset(&mut self) {
+        let show_broadphase = self.show_broadphase;
+        let show_mass_graph = self.show_mass_graph;
+        let show_separation_graph = self.show_separation_graph;
+        let ticks_before_reset = self.ticks_before_reset + self.tick_count.0;
+        *self = Core {
+            show_broadphase,
+            show_mass_graph,
+            show_separation_graph,
+            ticks_before_reset,
+            ..Core::with_recorders(
+                self.config.clone(),
+                self.event_log.take(),
+                self.trajectory_archive.take(),
+            )
+        };
+        self.init();
+    }
+
+    /// puts every selected body on a circular orbit around the sun at its current distance
+    pub(crate) fn circularize_selected(&mut self) {
+        let sun = match get_bodies(&self.world).into_iter().find(|body| body.sun) {
+            Some(sun) => sun,
+            None => return,
+        };
+        let query = <(
+            Read<Position>,
+            Write<Velocity>,
+            Read<Dimensions>,
+            Read<MetaInfo>,
+            Read<Data>,
+        )>::query();
+        for (position, mut velocity, dimensions, meta_info, data) in query.iter_mut(&mut self.world)
+        {
+            if meta_info.selected && !data.sun {
+                velocity.vector = circular_orbit_velocity(
+                    &position.point,
+                    dimensions.mass,
+                    &sun.position,
+                    sun.mass,
+                );
+            }
+        }
+        self.prediction = None;
+    }
+
+    /// runs exactly `ticks` ticks at the app's fixed time step, independent of any clock
+    pub(crate) fn advance(&mut self, ticks: u32) -> Result<(), Paused> {
+        if self.paused {
+            return Err(Paused);
+        }
+        for _ in 0..ticks {
+            self.tick(1. / UPS as f64);
+        }
+        Ok(())
+    }
+
+    /// moves the camera so every body fits on screen and returns the zoom to draw with
+    /// None when there's nothing to look at
+    pub(crate) fn overview(&mut self) -> Option<f64> {
+        let positions = get_bodies(&self.world)
+            .into_iter()
+            .map(|body| body.position)
+            .collect::<Vec<_>>();
+        let view = fit_to_bounds(&positions, self.config.overview_margin)?;
+        // drawing scales around the origin, so the center has to land on the scaled down screen center
+        let offset = screen_center() / view.zoom - view.center;
+        self.camera += offset - self.view_offset();
+        Some(view.zoom)
+    }
+
+    /// makes the cursor, given where it is on screen, pull on every body, None lets go
+    pub(crate) fn set_attractor(&mut self, cursor: Option<Vector2<f64>>) {
+        let offset = self.view_offset();
+        self.attractor = cursor.map(|cursor| Point2::from(cursor - offset));
+    }
+
+    pub(crate) fn toggle_mass_graph(&mut self) {
+        self.show_mass_graph = self.show_mass_graph.not();
+    }
+
+    pub(crate) fn toggle_separation_graph(&mut self) {
+        self.show_separation_graph = self.show_separation_graph.not();
+    }
+
+    pub(crate) fn toggle_broadphase_overlay(&mut self) {
+        self.show_broadphase = self.show_broadphase.not();
+    }
+
+    pub(crate) fn tick_count(&self) -> TickCount {
+        self.tick_count
+    }
+
+    /// bodies still around, the sun included
+    pub(crate) fn body_count(&self) -> usize {
+        get_bodies(&self.world).len()
+    }
+
+    /// mass of the heaviest body apart from the sun
+    pub(crate) fn largest_mass(&self) -> f64 {
+        largest_mass(&get_bodies(&self.world))
+    }
+
+    /// the heaviest body, the lower id on equal mass, the sun only counts with `include_sun`
+    pub(crate) fn largest_body(&self, include_sun: bool) -> Option<BodyView> {
+        self.iter_bodies()
+            .filter(|body| include_sun || !body.sun)
+            .min_by(|a, b| b.mass.total_cmp(&a.mass).then(a.id.cmp(&b.id)))
+    }
+
+    /// (lower id, higher id) of every pair of bodies that touched during the last tick,
+    /// whatever the collision then did to them, tests check detection with it
+    #[cfg(test)]
+    pub(crate) fn collision_pairs(&self) -> Vec<(i32, i32)> {
+        // the same pair can touch in more than one substep
+        self.impacts
+            .iter()
+            .map(|impact| impact.pair)
+            .sorted()
+            .dedup()
+            .collect()
+    }
+
+    /// every body, sun included, read out of the world one at a time instead of all up front
+    pub(crate) fn iter_bodies(&self) -> impl Iterator<Item = BodyView> + '_ {
+        self.world.iter_entities().filter_map(move |entity| {
+            let dimensions = self.world.get_component::<Dimensions>(entity)?;
+            Some(BodyView {
+                id: self.world.get_component::<Id>(entity)?.id,
+                position: self.world.get_component::<Position>(entity)?.point,
+                velocity: self.world.get_component::<Velocity>(entity)?.vector,
+                mass: dimensions.mass,
+                radius: dimensions.radius,
+                sun: self.world.get_component::<Data>(entity)?.sun,
+            })
+        })
+    }
+
+    /// `(id, fate)` of every body apart from the sun, going by its two body orbit around the sun
+    /// alone, empty without a sun
+    pub(crate) fn classify_bodies(&self) -> Vec<(i32, Fate)> {
+        let bodies = get_bodies(&self.world);
+        let sun = match bodies.iter().find(|body| body.sun) {
+            Some(sun) => sun,
+            None => return vec![],
+        };
+        bodies
+            .iter()
+            .filter(|body| !body.sun)
+            .map(|body| {
+                let offset = body.position - sun.position;
+                let velocity = body.velocity - sun.velocity;
+                let mu = GRAVITATIONAL_CONSTANT * body.mass * sun.mass;
+                let falling = offset.dot(&velocity) < 0.;
+                let fate = if falling
+                    && periapsis_distance(offset, velocity, mu) < sun.radius + body.radius
+                {
+                    Fate::Colliding
+                } else if is_unbound(offset, velocity, mu) {
+                    Fate::Escaping
+                } else {
+                    Fate::Bound
+                };
+                (body.id, fate)
+            })
+            .collect()
+    }
+
+    /// what gravity pulls with in the next tick, lower than `GRAVITATIONAL_CONSTANT` while it's ramping up
+    pub(crate) fn gravitational_constant(&self) -> f64 {
+        self.config
+            .gravity_ramp
+            .map_or(GRAVITATIONAL_CONSTANT, |ramp| {
+                ramp.constant_at(self.tick_count.0)
+            })
+    }
+
+    /// energy, momentum and barycenter of every body, sun included
+    pub(crate) fn diagnostics(&self) -> Diagnostics {
+        diagnostics(&get_bodies(&self.world), self.config.diagnostic_threads)
+    }
+
+    /// hash of every body's state, rounded to `state_hash_precision`, for checking a run against
+    /// a known result without keeping a whole snapshot around
+    /// the same on every platform and compiler version
+    pub(crate) fn state_hash(&self) -> u64 {
+        let precision = self.config.state_hash_precision;
+        let round = |value: f64| (value / precision).round() as i64;
+        get_bodies(&self.world)
+            .into_iter()
+            .sorted_by_key(|body| body.id)
+            .flat_map(|body| {
+                vec![
+                    body.id as i64,
+                    body.sun as i64,
+                    round(body.position.x),
+                    round(body.position.y),
+                    round(body.velocity.x),
+                    round(body.velocity.y),
+                    round(body.mass),
+                    round(body.radius),
+                ]
+            })
+            .fold(FNV_OFFSET_BASIS, |hash, value| {
+                fnv1a(hash, &value.to_le_bytes())
+            })
+    }
+
+    /// every body as one line of text, ordered by id and with floats printed exactly,
+    /// so two snapshots of the same state are byte for byte equal
+    #[cfg(test)]
+    pub(crate) fn snapshot(&self) -> String {
+        get_bodies(&self.world)
+            .into_iter()
+            .sorted_by_key(|body| body.id)
+            .map(|body| {
+                format!(
+                    "{} sun={} position=({:?}, {:?}) velocity=({:?}, {:?}) mass={:?} radius={:?} selected={}\n",
+                    body.id,
+                    body.sun,
+                    body.position.x,
+                    body.position.y,
+                    body.velocity.x,
+                    body.velocity.y,
+                    body.mass,
+                    body.radius,
+                    body.selected
+                )
+            })
+            .collect()
+    }
+}
+
+/// a body to place with `Core::spawn_body`
+pub(crate) struct BodySpec {
+    pub(crate) position: Point2<f64>,
+    pub(crate) velocity: Vector2<f64>,
+    pub(crate) mass: f64,
+    /// mass per unit volume, sets the radius together with the mass
+    pub(crate) density: f64,
+    /// defaults to the id
+    pub(crate) name: Option<String>,
+    /// `[r, g, b]`, drawn the usual way when None
+    pub(crate) color: Option<[u8; 3]>,
+    pub(crate) collision_mask: CollisionMask,
+    /// scales the bounce, see `Restitution`
+    pub(crate) restitution: Option<f64>,
+}
+
+/// what every body the scenario lists should be spawned as
+fn scenario_bodies(scenario: &Scenario) -> Vec<BodySpec> {
+    scenario
+        .bodies
+        .iter()
+        .map(|body| BodySpec {
+            position: Point2::from(body.position),
+            velocity: Vector2::from(body.velocity),
+            mass: body.mass,
+            density: body.density,
+            name: body.name.clone(),
+            color: body.color,
+            collision_mask: CollisionMask {
+                categories: body.categories,
+                collides_with: body.collides_with,
+            },
+            restitution: body.restitution,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BodyState {
+    pub(crate) name: String,
+    pub(crate) position: Point2<f64>,
+    pub(crate) velocity: Vector2<f64>,
+    pub(crate) mass: f64,
+    pub(crate) radius: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct BodyView {
+    pub(crate) id: i32,
+    pub(crate) position: Point2<f64>,
+    pub(crate) velocity: Vector2<f64>,
+    pub(crate) mass: f64,
+    pub(crate) radius: f64,
+    pub(crate) sun: bool,
+}
+
+pub(crate) struct Frame {
+    pub(crate) drawables: Vec<Drawable>,
+    /// expanding rings where bodies merged
+    pub(crate) shockwaves: Vec<Ring>,
+    /// glow to draw behind the sun, when turned on
+    pub(crate) corona: Option<Corona>,
+    /// around the sun, when turned on
+    pub(crate) hill_sphere: Option<Outline>,
+    pub(crate) predicted_orbit: Vec<Point2<f64>>,
+    pub(crate) time_to_impact: Option<TimeToImpact>,
+    pub(crate) grid_cells: Vec<GridCell>,
+    /// totals of the selected group when more than one body is selected
+    pub(crate) group: Option<Diagnostics>,
+    /// orbit around the sun when a single body is selected and it's bound
+    pub(crate) orbit: Option<OrbitalElements>,
+    /// `(tick, mass)` of the heaviest body apart from the sun, when the graph is shown
+    pub(crate) largest_mass_history: Vec<(u64, f64)>,
+    /// `(tick, distance)` between the two heaviest bodies apart from the sun, when the graph is shown
+    pub(crate) separation_history: Vec<(u64, f64)>,
+    pub(crate) measurement: Option<Measurement>,
+    /// where the selected bodies have been, oldest first
+    pub(crate) trails: Vec<Vec<Point2<f64>>>,
+    /// while paused in inspect mode
+    pub(crate) scrubber: Option<Scrubber>,
+}
+
+/// where the inspected moment is along the prediction
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Scrubber {
+    /// snapshot being drawn, None for the present
+    pub(crate) index: Option<usize>,
+    /// snapshots predicted so far
+    pub(crate) predicted: usize,
+    /// snapshots there'll be once the prediction is done
+    pub(crate) total: usize,
+    pub(crate) seconds_ahead: Option<f64>,
+}
+
+impl Frame {
+    /// moves everything that's drawn in world coordinates
+    fn offset_by(mut self, offset: Vector2<f64>) -> Frame {
+        for drawable in &mut self.drawables {
+            drawable.position += offset;
+        }
+        for point in &mut self.predicted_orbit {
+            *point += offset;
+        }
+        for cell in &mut self.grid_cells {
+            cell.corner += offset;
+        }
+        for shockwave in &mut self.shockwaves {
+            shockwave.position += offset;
+        }
+        if let Some(corona) = &mut self.corona {
+            corona.position += offset;
+        }
+        if let Some(hill_sphere) = &mut self.hill_sphere {
+            hill_sphere.position += offset;
+        }
+        for point in self.trails.iter_mut().flatten() {
+            *point += offset;
+        }
+        if let Some(measurement) = &mut self.measurement {
+            measurement.from += offset;
+            measurement.to += offset;
+        }
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Ring {
+    pub(crate) position: Point2<f64>,
+    pub(crate) radius: f64,
+    /// 1 when fresh, fading to 0
+    pub(crate) opacity: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Corona {
+    pub(crate) position: Point2<f64>,
+    pub(crate) radius: f64,
+}
+
+/// a circle drawn as a guide, not a body
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Outline {
+    pub(crate) position: Point2<f64>,
+    pub(crate) radius: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Diagnostics {
+    /// negative means the group is gravitationally bound
+    pub(crate) energy: f64,
+    pub(crate) momentum: Vector2<f64>,
+    pub(crate) barycenter: Point2<f64>,
+}
+
+/// where a body is headed if nothing but the sun pulls on it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Fate {
+    Bound,
+    Escaping,
+    /// falling into the sun before it comes back out
+    Colliding,
+}
+
+/// when the selected body is predicted to next hit something
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum TimeToImpact {
+    /// nothing within the prediction horizon
+    Stable,
+    Seconds(f64),
+}
+
+/// two bodies touching
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Impact {
+    /// (lower id, higher id)
+    pub(crate) pair: (i32, i32),
+    /// on the line between their centers, split in proportion to their radii
+    pub(crate) point: Point2<f64>,
+    /// how fast they were moving relative to each other
+    pub(crate) speed: f64,
+}
+
+impl Impact {
+    fn between(left: &Body, right: &Body) -> Impact {
+        let between = right.position - left.position;
+        Impact {
+            pair: (left.id, right.id),
+            point: left.position + between * left.radius / (left.radius + right.radius),
+            speed: (right.velocity - left.velocity).magnitude(),
+        }
+    }
+}
+
+/// what the measuring tool shows between its two ends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Measurement {
+    pub(crate) from: Point2<f64>,
+    pub(crate) to: Point2<f64>,
+    pub(crate) distance: f64,
+    /// only when both ends are bodies
+    pub(crate) motion: Option<RelativeMotion>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RelativeMotion {
+    /// velocity of the second body as seen from the first
+    pub(crate) velocity: Vector2<f64>,
+    /// how fast the distance between them shrinks, negative when they're moving apart
+    pub(crate) closing_speed: f64,
+}
+
+/// an occupied broadphase cell, for the debug overlay
+pub(crate) struct GridCell {
+    pub(crate) corner: Point2<f64>,
+    pub(crate) size: f64,
+    pub(crate) occupancy: usize,
+}
+
+pub(crate) struct Drawable {
+    pub(crate) position: Point2<f64>,
+    pub(crate) sun: bool,
+    pub(crate) radius: f64,
+    pub(crate) select_marker: bool,
+    pub(crate) label: Option<String>,
+    pub(crate) debris: bool,
+    /// `[r, g, b]` the body was given in a scenario or by its id, its age color,
+    /// or the escape color when it's unbound
+    pub(crate) color: Option<[u8; 3]>,
+    /// how brightly the sun lights this body, 0 to 1, None when lighting is off
+    pub(crate) illumination: Option<f64>,
+}
+
+/// `color` blended toward the heating color by how much of the ramp `accreted_mass` has covered
+fn heated_sun_color(color: [u8; 3], heating: &SunHeating, accreted_mass: f64) -> [u8; 3] {
+    let heat = (accreted_mass / heating.full_at).clamp(0., 1.);
+    blend(color, heating.color.0, heat)
+}
+
+/// where a body spawned at `spawn_tick` is along the age gradient at `tick`
+fn age_color(colors: &AgeColors, spawn_tick: u64, tick: u64) -> [u8; 3] {
+    let age = tick.saturating_sub(spawn_tick) as f64 / colors.old_at.get() as f64;
+    // the colors are evenly spaced, so the gradient is split into one stretch between each neighbouring pair
+    let stretches = colors.palette.len() - 1;
+    let position = age.min(1.) * stretches as f64;
+    let stretch = (position.floor() as usize).min(stretches - 1);
+    blend(
+        colors.palette[stretch].0,
+        colors.palette[stretch + 1].0,
+        position - stretch as f64,
+    )
+}
+
+/// `fraction` of the way from `from` to `to`, channel by channel
+fn blend(from: [u8; 3], to: [u8; 3], fraction: f64) -> [u8; 3] {
+    let mut blended = from;
+    for (channel, to) in blended.iter_mut().zip(to.iter()) {
+        *channel = (*channel as f64 + (*to as f64 - *channel as f64) * fraction).round() as u8;
+    }
+    blended
+}
+
+fn relative_motion(
+    position: &Point2<f64>,
+    velocity: &Vector2<f64>,
+    other_position: &Point2<f64>,
+    other_velocity: &Vector2<f64>,
+) -> RelativeMotion {
+    let relative_velocity = other_velocity - velocity;
+    let direction = (other_position - position)
+        .try_normalize(f64::EPSILON)
+        .unwrap_or_else(Vector2::zeros);
+    RelativeMotion {
+        velocity: relative_velocity,
+        closing_speed: -relative_velocity.dot(&direction),
+    }
+}
+
+/// `min_separation` floors the distance so close passes can't send the force off to infinity,
+/// `softening` smooths the force out at short range instead
+fn calculate_gravitational_force(
+    position: &Point2<f64>,
+    mass: &f64,
+    other_position: &Point2<f64>,
+    other_mass: &f64,
+    gravitational_constant: f64,
+    min_separation: f64,
+    softening: Option<Softening>,
+) -> Vector2<f64> {
+    let difference: Vector2<f64> = other_position - position;
+    let distance = difference.magnitude().max(min_separation);
+    let gravity_direction: Vector2<f64> = difference.normalize();
+    let gravity: f64 =
+        gravitational_constant * (mass * other_mass) * inverse_square(distance, softening);
+
+    gravity_direction * gravity
+}
+
+/// how the gravity on every body is summed up during a tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GravityMethod {
+    /// every pair of bodies, exact but quadratic
+    Exact,
+    /// Barnes-Hut, approximate but n log n
+    Tree,
+}
+
+/// the tree only pays for building itself once there are `threshold` bodies or more
+fn gravity_method(body_count: usize, threshold: Option<usize>) -> GravityMethod {
+    match threshold {
+        Some(threshold) if body_count >= threshold => GravityMethod::Tree,
+        _ => GravityMethod::Exact,
+    }
+}
+
+/// `vector` shortened to `max` if it's any longer, direction kept
+fn limit_magnitude(vector: Vector2<f64>, max: f64) -> Vector2<f64> {
+    let magnitude = vector.magnitude();
+    if magnitude > max {
+        vector * (max / magnitude)
+    } else {
+        vector
+    }
+}
+
+/// `1 / r²`, smoothed out below the softening length
+fn inverse_square(distance: f64, softening: Option<Softening>) -> f64 {
+    match softening {
+        None => 1. / (distance * distance),
+        Some(Softening::Plummer { length }) => {
+            distance / (distance * distance + length * length).powf(1.5)
+        }
+        Some(Softening::Spline { length }) if distance >= length => 1. / (distance * distance),
+        // the cubic spline kernel as used in gadget-2, force goes to 0 at the center
+        Some(Softening::Spline { length }) => {
+            let u = distance / length;
+            let kernel = if u < 0.5 {
+                32. / 3. + u * u * (32. * u - 38.4)
+            } else {
+                64. / 3. - 48. * u + 38.4 * u * u - 32. / 3. * u * u * u - 1. / 15. / (u * u * u)
+            };
+            distance * kernel / length.powi(3)
+        }
+    }
+}
+
+fn interpolate(previous: Point2<f64>, current: Point2<f64>, fraction: f64) -> Point2<f64> {
+    previous + (current - previous) * fraction
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct View {
+    zoom: f64,
+    center: Point2<f64>,
+}
+
+/// zoom and center that fit every point on screen with `margin` pixels to spare on each side
+fn fit_to_bounds(points: &[Point2<f64>], margin: f64) -> Option<View> {
+    let first = points.first()?;
+    let (min, max) = points.iter().fold((*first, *first), |(min, max), point| {
+        (
+            Point2::new(min.x.min(point.x), min.y.min(point.y)),
+            Point2::new(max.x.max(point.x), max.y.max(point.y)),
+        )
+    });
+    let size = max - min;
+    // a lone body or a straight line has no extent along some axis, that axis doesn't limit the zoom
+    let zoom = ((WIDTH as f64 - 2. * margin) / size.x)
+        .min((HEIGHT as f64 - 2. * margin) / size.y)
+        .min(MAX_OVERVIEW_ZOOM);
+    Some(View {
+        zoom,
+        center: min + size / 2.,
+    })
+}
+
+/// a fresh sequence every run when there's no seed
+fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+fn screen_center() -> Point2<f64> {
+    Point2::new((WIDTH / 2.).into(), (HEIGHT / 2.).into())
+}
+
+/// pull toward the cursor, a real acceleration like the background potential
+/// a negative strength pushes bodies away instead
+fn cursor_acceleration(
+    attractor: &CursorAttractor,
+    position: &Point2<f64>,
+    cursor: &Point2<f64>,
+) -> Vector2<f64> {
+    let offset = cursor - position;
+    let distance = offset.magnitude();
+    if distance < f64::EPSILON {
+        return Vector2::zeros();
+    }
+    offset / distance * attractor.strength / distance.powf(attractor.falloff)
+}
+
+/// pull of the smooth background potential, centered where the sun starts
+/// unlike body gravity this is a real acceleration, independent of the body's mass
+fn background_acceleration(
+    potential: &BackgroundPotential,
+    position: &Point2<f64>,
+) -> Vector2<f64> {
+    let offset: Vector2<f64> = position - screen_center();
+    let distance_squared = offset.magnitude_squared();
+    match *potential {
+        BackgroundPotential::Logarithmic {
+            circular_speed,
+            core_radius,
+        } => -offset * circular_speed.powi(2) / (distance_squared + core_radius.powi(2)),
+        BackgroundPotential::PointMass { mass } => {
+            if distance_squared == 0. {
+                return Vector2::new(0., 0.);
+            }
+            -offset * GRAVITATIONAL_CONSTANT * mass / distance_squared.powf(1.5)
+        }
+    }
+}
+
+/// potential energy of a pair, consistent with `calculate_gravitational_force`
+fn potential_energy(
+    position: &Point2<f64>,
+    mass: f64,
+    other_position: &Point2<f64>,
+    other_mass: f64,
+) -> f64 {
+    let distance = (other_position - position).magnitude();
+    -GRAVITATIONAL_CONSTANT * mass * other_mass / distance
+}
+
+/// brief ring left where a body got swallowed
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Shockwave {
+    position: Point2<f64>,
+    // when it started
+    radius: f64,
+    age: u32,
+}
+
+/// of every body, the sun included
+fn kinetic_energy(bodies: &[Body]) -> f64 {
+    bodies
+        .iter()
+        .map(|body| 0.5 * body.mass * body.velocity.magnitude_squared())
+        .sum()
+}
+
+/// kinetic energy of a swallowed body in the frame of whoever swallowed it
+fn impact_energy(mass: f64, velocity: Vector2<f64>, survivor_velocity: Vector2<f64>) -> f64 {
+    0.5 * mass * (velocity - survivor_velocity).magnitude_squared()
+}
+
+fn shockwave_radius(impact_energy: f64) -> f64 {
+    SHOCKWAVE_SIZE * impact_energy.cbrt()
+}
+
+/// `multiple` times the sun's radius, pulsing a little over time
+fn corona_radius(sun_radius: f64, multiple: f64, tick_count: TickCount) -> f64 {
+    let pulse = 1. + CORONA_PULSE * (tick_count.0 as f64 * CORONA_PULSE_SPEED).sin();
+    sun_radius * multiple * pulse
+}
+
+/// sunlight falls off with the square of the distance, everything within `sqrt(luminosity)` is fully lit
+fn illumination(luminosity: f64, distance: f64) -> f64 {
+    (luminosity / distance.powi(2)).min(1.)
+}
+
+/// kinetic plus pairwise potential energy of a group, negative when it's gravitationally bound
+/// gravity isn't divided by the attracted body's mass, so the conserved kinetic term is per unit mass
+fn total_energy(bodies: &[Body], threads: usize) -> f64 {
+    let kinetic = pairwise_sum(bodies.len(), threads, |i| {
+        0.5 * bodies[i].velocity.magnitude_squared()
+    });
+    // each term is one body's interaction with everyone after it, so every pair is counted once
+    let potential = pairwise_sum(bodies.len(), threads, |i| {
+        let body = &bodies[i];
+        bodies[i + 1..]
+            .iter()
+            .map(|other| potential_energy(&body.position, body.mass, &other.position, other.mass))
+            .sum::<f64>()
+    });
+    kinetic + potential
+}
+
+/// energy, momentum and barycenter of a group
+/// all sums go through `pairwise_sum`, so the totals don't depend on the number of threads
+fn diagnostics(bodies: &[Body], threads: usize) -> Diagnostics {
+    let sum = |term: &(dyn Fn(&Body) -> f64 + Sync)| {
+        pairwise_sum(bodies.len(), threads, |i| term(&bodies[i]))
+    };
+    Diagnostics {
+        energy: total_energy(bodies, threads),
+        momentum: Vector2::new(
+            sum(&|body| body.mass * body.velocity.x),
+            sum(&|body| body.mass * body.velocity.y),
+        ),
+        barycenter: barycenter(bodies, threads),
+    }
+}
+
+fn barycenter(bodies: &[Body], threads: usize) -> Point2<f64> {
+    let sum = |term: &(dyn Fn(&Body) -> f64 + Sync)| {
+        pairwise_sum(bodies.len(), threads, |i| term(&bodies[i]))
+    };
+    let mass = sum(&|body| body.mass);
+    Point2::new(
+        sum(&|body| body.mass * body.position.x) / mass,
+        sum(&|body| body.mass * body.position.y) / mass,
+    )
+}
+
+/// velocity that puts a body on a circular, counter clockwise orbit around `center_position`
+/// gravity isn't divided by the mass of the attracted body, so that mass is part of the orbital speed
+fn circular_orbit_velocity(
+    position: &Point2<f64>,
+    mass: f64,
+    center_position: &Point2<f64>,
+    center_mass: f64,
+) -> Vector2<f64> {
+    let offset: Vector2<f64> = position - center_position;
+    let radius = offset.magnitude();
+    if radius == 0. {
+        return Vector2::new(0., 0.);
+    }
+    let speed = (GRAVITATIONAL_CONSTANT * mass * center_mass / radius).sqrt();
+    Vector2::new(-offset.y, offset.x) / radius * speed
+}
+
+fn are_colliding(
+    position: Point2<f64>,
+    radius: f64,
+    other_position: Point2<f64>,
+    other_radius: f64,
+    margin: f64,
+) -> bool {
+    let shape = Ball::new(radius);
+    let position = Isometry2::new(position.coords, nalgebra::zero());
+    let other_shape = Ball::new(other_radius);
+    let other_position = Isometry2::new(other_position.coords, nalgebra::zero());
+
+    match query::proximity(&position, &shape, &other_position, &other_shape, margin) {
+        Proximity::Intersecting => true,
+        // just touching doesn't count without a margin
+        Proximity::WithinMargin => margin > 0.,
+        Proximity::Disjoint => false,
+    }
+}
+
+/// how far two circles reach into each other, negative when they're apart
+fn overlap_depth(
+    position: Point2<f64>,
+    radius: f64,
+    other_position: Point2<f64>,
+    other_radius: f64,
+) -> f64 {
+    radius + other_radius - (other_position - position).magnitude()
+}
+
+fn get_bodies(world: &World) -> Vec<Body> {
+    <(
+        Read<Position>,
+        Read<Velocity>,
+        Read<Dimensions>,
+        Read<MetaInfo>,
+        Read<Id>,
+        Read<Data>,
+    )>::query()
+    .iter_entities(world)
+    .map(
+        |(entity, (pos, velocity, dimensions, meta_info, id, data))| Body {
+            position: pos.point,
+            velocity: velocity.vector,
+            radius: dimensions.radius,
+            mass: dimensions.mass,
+            selected: meta_info.selected,
+            id: id.id,
+            sun: data.sun,
+            frozen: world.get_component::<Static>(entity).is_some(),
+            collision_mask: world
+                .get_component::<CollisionMask>(entity)
+                .map(|mask| *mask)
+                .unwrap_or_default(),
+            restitution: world
+                .get_component::<Restitution>(entity)
+                .map_or(1., |restitution| restitution.0),
+            delete: false,
+            absorbed_by: None,
+        },
+    )
+    .collect::<Vec<_>>()
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// a color of its own for every id, the same on every run and platform
+pub(crate) fn id_color(id: i32) -> [u8; 3] {
+    hue_color(id_hue(id))
+}
+
+/// 0 to 1, neighbouring ids land far apart
+fn id_hue(id: i32) -> f64 {
+    let hash = fnv1a(FNV_OFFSET_BASIS, &id.to_le_bytes());
+    // four bytes aren't enough for FNV to reach the high bits, so they're stirred some more
+    let hash = (hash ^ (hash >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    let hash = hash ^ (hash >> 33);
+    (hash >> 40) as f64 / (1 << 24) as f64
+}
+
+/// a pastel at `hue`, 0 to 1 around the color wheel starting at red, light enough to show up on black
+fn hue_color(hue: f64) -> [u8; 3] {
+    const SATURATION: f64 = 0.6;
+    // hsv with full value, each channel drops off as the hue moves away from where it peaks
+    let channel = |offset: f64| {
+        let sector = (offset + hue * 6.) % 6.;
+        let drop = sector.min(4. - sector).clamp(0., 1.);
+        ((1. - SATURATION * drop) * 255.).round() as u8
+    };
+    [channel(5.), channel(3.), channel(1.)]
+}
+
+/// FNV-1a, which unlike the std hasher is pinned down and won't change between compiler versions
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn largest_mass(bodies: &[Body]) -> f64 {
+    bodies
+        .iter()
+        .filter(|body| !body.sun)
+        .map(|body| body.mass)
+        .fold(0., f64::max)
+}
+
+/// the heaviest and second heaviest body apart from the sun, the lower id first on equal mass
+fn two_heaviest(bodies: &[Body]) -> Option<(&Body, &Body)> {
+    heaviest_pair(bodies.iter().filter(|body| !body.sun))
+}
+
+fn heaviest_pair<'a>(bodies: impl Iterator<Item = &'a Body>) -> Option<(&'a Body, &'a Body)> {
+    let mut candidates = bodies.collect::<Vec<_>>();
+    candidates.sort_by(|a, b| b.mass.total_cmp(&a.mass).then(a.id.cmp(&b.id)));
+    match candidates.as_slice() {
+        [first, second, ..] => Some((first, second)),
+        _ => None,
+    }
+}
+
+/// frame turning along with the two heaviest bodies, sun included, so that they stand still in it
+struct RotatingFrame {
+    held: (i32, i32),
+    /// gravity pulls both of them equally hard, so they circle the point halfway between them
+    center: Point2<f64>,
+    /// counter clockwise, squared and per unit mass of whatever moves through the frame,
+    /// gravity grows with the mass it pulls on, so a heavier body has to turn faster to keep up
+    angular_velocity_squared_per_mass: f64,
+}
+
+impl RotatingFrame {
+    fn new(bodies: &[Body]) -> Option<RotatingFrame> {
+        let (first, second) = heaviest_pair(bodies.iter())?;
+        let distance = (second.position - first.position).magnitude();
+        if distance <= 0. {
+            return None;
+        }
+        // a body of mass m is pulled as if G were G·m, which has the pair circle at ω² = G·m·(m₁ + m₂)/d³
+        let angular_velocity_squared_per_mass =
+            GRAVITATIONAL_CONSTANT * (first.mass + second.mass) / distance.powi(3);
+        Some(RotatingFrame {
+            held: (first.id, second.id),
+            center: nalgebra::center(&first.position, &second.position),
+            angular_velocity_squared_per_mass,
+        })
+    }
+
+    fn holds(&self, id: i32) -> bool {
+        self.held.0 == id || self.held.1 == id
+    }
+
+    /// centrifugal plus coriolis acceleration of a body of `mass` moving through the frame
+    fn pseudo_acceleration(
+        &self,
+        mass: f64,
+        position: &Point2<f64>,
+        velocity: &Vector2<f64>,
+    ) -> Vector2<f64> {
+        let angular_velocity_squared = self.angular_velocity_squared_per_mass * mass;
+        let centrifugal = (position - self.center) * angular_velocity_squared;
+        // -2ω × v
+        let coriolis = Vector2::new(velocity.y, -velocity.x) * 2. * angular_velocity_squared.sqrt();
+        centrifugal + coriolis
+    }
+}
+
+/// bodies are widened by half the contact margin, so any that count as touching share a cell
+fn build_broadphase(cell_size: f64, contact_margin: f64, bodies: &[Body]) -> SpatialGrid {
+    let mut grid = SpatialGrid::new(cell_size);
+    for body in bodies {
+        grid.insert(body.id, body.position, body.radius + contact_margin / 2.);
+    }
+    grid
+}
+
+struct Prediction {
+    path: Vec<Point2<f64>>,
+    // where every predicted body is at each point of the path, only kept in inspect mode
+    snapshots: Vec<HashMap<i32, Point2<f64>>>,
+    // None when nothing is selected
+    time_to_impact: Option<TimeToImpact>,
+    // where the prediction has got to, so it can carry on in a later frame
+    bodies: Vec<Body>,
+    time_step: f64,
+    steps: usize,
+    horizon: usize,
+    sample_interval: usize,
+}
+
+impl Prediction {
+    fn new(time_step: f64, world: &World, config: &Config) -> Prediction {
+        let mut bodies = get_bodies(world);
+        if let Some(limit) = config.prediction_body_limit {
+            bodies.sort_by(|a, b| b.mass.total_cmp(&a.mass).then(a.id.cmp(&b.id)));
+            let mut others = 0;
+            bodies.retain(|body| {
+                others += !body.selected as usize;
+                body.selected || others <= limit
+            });
+        }
+        Prediction {
+            path: vec![],
+            snapshots: vec![],
+            time_to_impact: bodies
+                .iter()
+                .find(|body| body.selected)
+                .map(|_| TimeToImpact::Stable),
+            bodies,
+            time_step,
+            steps: 0,
+            horizon: config.prediction_steps,
+            sample_interval: config.prediction_sample_interval.get(),
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.steps >= self.horizon
+    }
+
+    /// only stable once the whole horizon has been checked
+    fn time_to_impact(&self) -> Option<TimeToImpact> {
+        match self.time_to_impact {
+            Some(TimeToImpact::Stable) if !self.is_complete() => None,
+            time_to_impact => time_to_impact,
+        }
+    }
+
+    /// predicts further until it's done or `deadline` has passed,
+    /// a point is added to the path on every call so it always gets somewhere
+    fn extend(&mut self, config: &Config, deadline: Option<Instant>) {
+        while !self.is_complete() {
+            let i = self.steps;
+            let mass_before = self
+                .bodies
+                .iter()
+                .find(|body| body.selected)
+                .map(|body| body.mass);
+            let bodies = do_one_physics_step(self.time_step, self.bodies.split_off(0), config);
+            // either swallowed or swallowing something
+            let selected = bodies.iter().find(|body| body.selected);
+            if let (Some(TimeToImpact::Stable), Some(body), Some(mass_before)) =
+                (self.time_to_impact, selected, mass_before)
+            {
+                if body.delete || body.mass != mass_before {
+                    self.time_to_impact =
+                        Some(TimeToImpact::Seconds((i + 1) as f64 * self.time_step));
+                }
+            }
+            self.bodies = bodies
+                .into_iter()
+                .filter(|body| !body.delete)
+                .collect::<Vec<_>>();
+            self.steps += 1;
+            if i.is_multiple_of(self.sample_interval) {
+                let maybe_selected = self.bodies.iter().find(|body| body.selected);
+                if let Some(body) = maybe_selected {
+                    self.path.push(body.position);
+                }
+                if config.inspect {
+                    self.snapshots.push(
+                        self.bodies
+                            .iter()
+                            .map(|body| (body.id, body.position))
+                            .collect(),
+                    );
+                }
+                if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// intermediare struct to pass a body around
+#[derive(Clone, Debug)]
+struct Body {
+    position: Point2<f64>,
+    velocity: Vector2<f64>,
+    radius: f64,
+    mass: f64,
+    selected: bool,
+    id: i32,
+    sun: bool,
+    frozen: bool,
+    collision_mask: CollisionMask,
+    // 1 when the body has no `Restitution`
+    restitution: f64,
+    delete: bool,
+    // id of the body that swallowed this one, the sun wins if there's several
+    absorbed_by: Option<i32>,
+}
+
+/// a step at full gravity, predictions don't follow the ramp
+fn do_one_physics_step(time_step: f64, bodies: Vec<Body>, config: &Config) -> Vec<Body> {
+    physics_step(time_step, bodies, config, GRAVITATIONAL_CONSTANT).0
+}
+
+/// every pair of bodies that touch and are allowed to collide, lower id first and in id order
+fn detect_collisions(bodies: &[Body], cell_size: f64, contact_margin: f64) -> Vec<(i32, i32)> {
+    let by_id = bodies
+        .iter()
+        .map(|body| (body.id, body))
+        .collect::<HashMap<_, _>>();
+    let mut pairs = build_broadphase(cell_size, contact_margin, bodies)
+        .neighbours()
+        .into_iter()
+        .flat_map(|(id, others)| {
+            others
+                .into_iter()
+                .filter(move |other| id != *other)
+                .map(move |other| (id.min(other), id.max(other)))
+        })
+        // bodies spanning several cells meet in each of them, every pair is only handled once
+        .unique()
+        .filter(|(left, right)| {
+            let (left, right) = (by_id[left], by_id[right]);
+            left.collision_mask.interacts_with(&right.collision_mask)
+                && are_colliding(
+                    left.position,
+                    left.radius,
+                    right.position,
+                    right.radius,
+                    contact_margin,
+                )
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// like `do_one_physics_step`, but also hands back where bodies were found touching
+fn physics_step(
+    time_step: f64,
+    mut bodies: Vec<Body>,
+    config: &Config,
+    gravitational_constant: f64,
+) -> (Vec<Body>, Vec<Impact>) {
+    // calculate new velocities
+    let mut clones = bodies.clone();
+    // float addition isn't associative, so the order the pulls are added up in shows in the result
+    if config.deterministic_sum {
+        clones.sort_by_key(|clone| clone.id);
+    }
+    let index_of = clones
+        .iter()
+        .enumerate()
+        .map(|(index, body)| (body.id, index))
+        .collect::<HashMap<_, _>>();
+    let cutoff_grid = config.gravity_cutoff.map(|cutoff| {
+        let mut grid = SpatialGrid::new(cutoff);
+        for body in &clones {
+            grid.insert(body.id, body.position, 0.);
+        }
+        (cutoff, grid)
+    });
+    // the cutoff already keeps gravity from being quadratic
+    let tree = match gravity_method(clones.len(), config.tree_gravity_threshold) {
+        GravityMethod::Tree if cutoff_grid.is_none() => Some(QuadTree::new(
+            clones
+                .iter()
+                .map(|clone| (clone.position, clone.mass))
+                .collect(),
+        )),
+        _ => None,
+    };
+    let frame = if config.rotating_frame {
+        RotatingFrame::new(&clones)
+    } else {
+        None
+    };
+    let held =
+        |body: &Body| body.frozen || frame.as_ref().is_some_and(|frame| frame.holds(body.id));
+    bodies = bodies
+        .into_iter()
+        .map(|mut body| {
+            if held(&body) {
+                body.velocity = Vector2::zeros();
+                return body;
+            }
+            let pull = |other_position: &Point2<f64>, other_mass: f64| {
+                calculate_gravitational_force(
+                    &body.position,
+                    &body.mass,
+                    other_position,
+                    &other_mass,
+                    gravitational_constant,
+                    config.min_separation,
+                    config.softening,
+                )
+            };
+            let mut acceleration = Vector2::zeros();
+            match (&tree, &cutoff_grid) {
+                _ if body.sun => {}
+                (Some(tree), _) => acceleration += tree.pull(index_of[&body.id], pull),
+                (None, Some((cutoff, grid))) => {
+                    for id in grid.nearby(body.position, *cutoff) {
+                        let clone = &clones[index_of[&id]];
+                        if body.id != clone.id
+                            && (clone.position - body.position).magnitude() <= *cutoff
+                        {
+                            acceleration += pull(&clone.position, clone.mass);
+                        }
+                    }
+                }
+                (None, None) => {
+                    for clone in clones.iter().filter(|clone| clone.id != body.id) {
+                        acceleration += pull(&clone.position, clone.mass);
+                    }
+                }
+            }
+            if let (Some(potential), false) = (&config.background_potential, body.sun) {
+                acceleration += background_acceleration(potential, &body.position);
+            }
+            if let Some(max_acceleration) = config.max_acceleration {
+                acceleration = limit_magnitude(acceleration, max_acceleration);
+            }
+            if let (Some(frame), false) = (&frame, body.sun) {
+                acceleration +=
+                    frame.pseudo_acceleration(body.mass, &body.position, &body.velocity);
+            }
+            body.velocity += acceleration * time_step;
+            body
+        })
+        .collect::<Vec<_>>();
+    // move bodies
+    bodies = bodies
+        .into_iter()
+        .map(|mut body| {
+            if !held(&body) {
+                body.position += body.velocity * time_step;
+            }
+            body
+        })
+        .collect::<Vec<_>>();
+
+    let collisions = detect_collisions(&bodies, config.broadphase_cell_size, config.contact_margin);
+    let mut touching = HashMap::<i32, Vec<i32>>::new();
+    for (left, right) in &collisions {
+        touching.entry(*left).or_default().push(*right);
+        touching.entry(*right).or_default().push(*left);
+    }
+    let clones = bodies
+        .iter()
+        .map(|body| (body.id, body.clone()))
+        .collect::<HashMap<_, _>>();
+    let impacts = collisions
+        .iter()
+        .map(|&(left, right)| Impact::between(&clones[&left], &clones[&right]))
+        .collect::<Vec<_>>();
+    // (swallowed, survivor) pairs
+    let mut merges = vec![];
+    bodies = bodies
+        .into_iter()
+        .map(|mut body| {
+            let partners = touching.get(&body.id).into_iter().flatten();
+            for clone in partners.map(|id| &clones[id]) {
+                if body.sun {
+                    continue;
+                }
+                if let (true, Some(capture)) = (clone.sun, &config.sun_capture) {
+                    let relative = body.velocity - clone.velocity;
+                    let normal = (body.position - clone.position).try_normalize(1e-12);
+                    if let (true, Some(normal)) = (relative.magnitude() < capture.speed, normal) {
+                        // lifted back onto the surface, with nothing left heading inward
+                        body.position = clone.position + normal * (clone.radius + body.radius);
+                        let along_surface = relative - normal * relative.dot(&normal).min(0.);
+                        body.velocity = clone.velocity + along_surface * (1. - capture.damping);
+                        continue;
+                    }
+                }
+                let overlap =
+                    overlap_depth(body.position, body.radius, clone.position, clone.radius);
+                let deep_enough =
+                    overlap > config.merge_overlap_fraction * body.radius.min(clone.radius);
+                let merge = match config.collision_mode {
+                    CollisionMode::Merge => deep_enough,
+                    CollisionMode::Bounce { restitution } => {
+                        let normal = (clone.position - body.position).try_normalize(1e-12);
+                        let closing_speed = normal
+                            .map(|normal| (body.velocity - clone.velocity).dot(&normal))
+                            .unwrap_or_default();
+                        let restitution =
+                            restitution.at(closing_speed) * body.restitution * clone.restitution;
+                        match normal {
+                            // already moving apart, leave them be
+                            _ if closing_speed <= 0. => false,
+                            Some(normal) if restitution > 0. || !deep_enough => {
+                                body.velocity -= normal
+                                    * closing_speed
+                                    * (1. + restitution)
+                                    * (clone.mass / (body.mass + clone.mass));
+                                false
+                            }
+                            _ => deep_enough,
+                        }
+                    }
+                };
+                // the bigger body swallows the smaller one, on equal mass the lower id survives
+                // the sun never gets here, so it's only ever seen from the smaller body's side
+                let bigger =
+                    body.mass > clone.mass || (body.mass == clone.mass && body.id < clone.id);
+                if merge && !bigger {
+                    merges.push((body.id, clone.id));
+                }
+            }
+            body
+        })
+        .collect::<Vec<_>>();
+
+    // one survivor per swallowed body, the sun has the lowest id so it wins if it's among them
+    merges.sort_unstable();
+    merges.dedup_by_key(|(swallowed, _)| *swallowed);
+    // whatever doesn't fit this time still overlaps next time, and gets another go then
+    if let Some(max_merges) = config.max_merges_per_tick {
+        merges.truncate(max_merges);
+    }
+    // every merge takes one body away, and what isn't merged just passes through
+    if let Some(min_bodies) = config.min_bodies {
+        merges.truncate(bodies.len().saturating_sub(min_bodies));
+    }
+    // a survivor can be swallowed in turn, everything ends up in the last body along the chain
+    let next_survivor = merges.iter().copied().collect::<HashMap<_, _>>();
+    let merges = merges
+        .into_iter()
+        .map(|(swallowed, mut survivor)| {
+            while let Some(next) = next_survivor.get(&survivor) {
+                survivor = *next;
+            }
+            (swallowed, survivor)
+        })
+        .collect::<Vec<_>>();
+    let mut swallowed_by = HashMap::<i32, Vec<&Body>>::new();
+    for (swallowed, survivor) in &merges {
+        swallowed_by
+            .entry(*survivor)
+            .or_default()
+            .push(&clones[swallowed]);
+    }
+    let merges = merges.iter().copied().collect::<HashMap<_, _>>();
+    let sun = clones.values().find(|body| body.sun);
+    bodies = bodies
+        .into_iter()
+        .map(|mut body| {
+            if let Some(survivor) = merges.get(&body.id) {
+                body.delete = true;
+                body.absorbed_by = Some(*survivor);
+            }
+            // the sun only grows when it's allowed to
+            match swallowed_by.get(&body.id) {
+                Some(swallowed) if body.sun => {
+                    if let Some(compact_mass) = config.compact_sun_mass {
+                        body.mass = swallowed
+                            .iter()
+                            .fold(body.mass, |mass, clone| mass + clone.mass);
+                        // heavy enough to be compact, it keeps pulling harder but no longer gets bigger
+                        body.radius = body.radius.max(
+                            Dimensions::from_mass(body.mass.min(compact_mass), config.radius_model)
+                                .radius,
+                        );
+                    }
+                }
+                Some(swallowed) if !body.sun => {
+                    // already in id order, so the result doesn't depend on the order partners were found in
+                    let mass = swallowed
+                        .iter()
+                        .fold(body.mass, |mass, clone| mass + clone.mass);
+                    let momentum = swallowed
+                        .iter()
+                        .fold(body.velocity * body.mass, |momentum, clone| {
+                            momentum + clone.velocity * clone.mass
+                        });
+                    let angular_momentum =
+                        sun.filter(|_| config.conserve_angular_momentum).map(|sun| {
+                            let total = swallowed
+                                .iter()
+                                .fold(angular_momentum_about(&body, sun), |total, clone| {
+                                    total + angular_momentum_about(clone, sun)
+                                });
+                            (sun, total)
+                        });
+                    body.velocity = momentum / mass;
+                    if let Some((sun, angular_momentum)) = angular_momentum {
+                        let offset = body.position - sun.position;
+                        let distance = offset.magnitude();
+                        if distance > 0. {
+                            // only the part along the orbit carries angular momentum
+                            let along = Vector2::new(-offset.y, offset.x) / distance;
+                            let relative = body.velocity - sun.velocity;
+                            let missing = angular_momentum - mass * offset.perp(&relative);
+                            body.velocity += along * missing / (mass * distance);
+                        }
+                    }
+                    // grows as dense as it was
+                    let density = Dimensions {
+                        mass: body.mass,
+                        radius: body.radius,
+                    }
+                    .density(config.radius_model);
+                    body.radius =
+                        Dimensions::from_mass_and_density(mass, density, config.radius_model)
+                            .radius;
+                    body.mass = mass;
+                    if let (Some(disk), Some(sun)) = (&config.accretion_disk, sun) {
+                        if (body.position - sun.position).magnitude() <= disk.radius {
+                            let offset = body.position - sun.position;
+                            let relative = body.velocity - sun.velocity;
+                            let circular = circular_orbit_velocity(
+                                &body.position,
+                                body.mass,
+                                &sun.position,
+                                sun.mass,
+                            );
+                            // keep going round the way it already was
+                            let circular = if offset.perp(&relative) < 0. {
+                                -circular
+                            } else {
+                                circular
+                            };
+                            body.velocity += (sun.velocity + circular - body.velocity) * disk.nudge;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            body
+        })
+        .collect();
+    (bodies, impacts)
+}
+
+/// adds where every selected body is now to its trail, keeping the last `length` positions,
+/// and forgets the trails of bodies that are no longer selected or gone
+fn record_trails(trails: &mut HashMap<i32, VecDeque<Point2<f64>>>, bodies: &[Body], length: usize) {
+    let selected = bodies
+        .iter()
+        .filter(|body| body.selected)
+        .map(|body| (body.id, body.position))
+        .collect::<HashMap<_, _>>();
+    trails.retain(|id, _| selected.contains_key(id));
+    for (id, position) in selected {
+        let trail = trails.entry(id).or_default();
+        trail.push_back(position);
+        while trail.len() > length {
+            trail.pop_front();
+        }
+    }
+}
+
+/// orbital angular momentum of `body` about `center`
+fn angular_momentum_about(body: &Body, center: &Body) -> f64 {
+    body.mass * (body.position - center.position).perp(&(body.velocity - center.velocity))
+}
+
+/// panics if a physics step created or destroyed mass, apart from what the sun swallowed without growing
+#[cfg(debug_assertions)]
+fn assert_mass_conserved(before: &[Body], after: &[Body]) {
+    let total_before: f64 = before.iter().map(|body| body.mass).sum();
+    let surviving: f64 = after
+        .iter()
+        .filter(|body| !body.delete)
+        .map(|body| body.mass)
+        .sum();
+    // unless the sun grows, mass it swallows leaves the system
+    let suns = after
+        .iter()
+        .filter(|body| body.sun)
+        .map(|body| body.id)
+        .collect::<Vec<_>>();
+    let accreted: f64 = after
+        .iter()
+        .filter(|body| body.delete)
+        .filter(|body| body.absorbed_by.is_some_and(|id| suns.contains(&id)))
+        .map(|body| body.mass)
+        .sum();
+    let sun_mass = |bodies: &[Body]| {
+        bodies
+            .iter()
+            .filter(|body| body.sun)
+            .map(|body| body.mass)
+            .sum::<f64>()
+    };
+    let sun_growth = sun_mass(after) - sun_mass(before);
+    let drift = total_before - surviving - (accreted - sun_growth);
+    assert!(
+        drift.abs() <= total_before * 1e-9,
+        "mass budget drifted by {}: {} before, {} surviving, {} accreted by the sun",
+        drift,
+        total_before,
+        surviving,
+        accreted
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use nalgebra::{Isometry2, Point2, Vector2};
+    use ncollide2d::query::PointQuery;
+
+    use crate::archive::{read_archive, ArchivedTick};
+    use crate::config::{
+        AccretionDisk, Comets, Equilibrium, GravityRamp, HillSphere, Linear, RestitutionCurve,
+        SunCapture, VelocityBias,
+    };
+
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        let vector: Vector2<f64> = Vector2::new(11., 11.);
+        let vector1 = Vector2::new(10., 10.);
+
+        let result: Vector2<f64> = vector1 - vector;
+
+        let result = result.magnitude();
+
+        print!("{:?}", result)
+    }
+
+    #[test]
+    fn test_click_inside() {
+        let cuboid = Ball::new(1.);
+        let click_pos = Point2::from(Vector2::new(11., 20.));
+
+        let cuboid_pos = Isometry2::translation(10., 20.);
+
+        // Solid projection.
+        assert_eq!(cuboid.distance_to_point(&cuboid_pos, &click_pos, true), 0.0);
+    }
+
+    #[test]
+    fn tick_count_only_counts_unpaused_steps() {
+        let mut core = Core::new(Config::default());
+        core.init();
+        for _ in 0..10 {
+            core.tick(0.005);
+        }
+        assert_eq!(core.tick_count(), TickCount(10));
+
+        core.pause();
+        core.tick(0.005);
+        assert_eq!(core.tick_count(), TickCount(10));
+    }
+
+    /// ids of the bodies whose snapshot lines differ, including bodies only present on one side
+    fn diff_snapshots(left: &str, right: &str) -> Vec<i32> {
+        let by_id = |snapshot: &str| {
+            snapshot
+                .lines()
+                .map(|line| {
+                    let id = line.split(' ').next().unwrap().parse::<i32>().unwrap();
+                    (id, line.to_string())
+                })
+                .collect::<HashMap<_, _>>()
+        };
+        let left = by_id(left);
+        let right = by_id(right);
+        left.keys()
+            .chain(right.keys())
+            .unique()
+            .filter(|id| left.get(id) != right.get(id))
+            .copied()
+            .sorted()
+            .collect()
+    }
+
+    fn spawn(core: &mut Core, body: &Body) {
+        core.world.insert(
+            (),
+            vec![(
+                Data {
+                    name: body.id.to_string(),
+                    sun: body.sun,
+                    color: None,
+                },
+                Position {
+                    point: body.position,
+                },
+                Velocity {
+                    vector: body.velocity,
+                },
+                Dimensions {
+                    radius: body.radius,
+                    mass: body.mass,
+                },
+                MetaInfo {
+                    selected: body.selected,
+                },
+                Id { id: body.id },
+            )],
+        );
+    }
+
+    fn body(id: i32, x: f64, y: f64, mass: f64) -> Body {
+        Body {
+            position: Point2::new(x, y),
+            velocity: Vector2::new(0., 0.),
+            radius: Dimensions::from_mass(mass, RadiusModel::Sphere).radius,
+            mass,
+            selected: false,
+            id,
+            sun: false,
+            frozen: false,
+            collision_mask: CollisionMask::default(),
+            restitution: 1.,
+            delete: false,
+            absorbed_by: None,
+        }
+    }
+
+    #[test]
+    fn unperturbed_orbital_velocity_gives_circular_orbits() {
+        let sun_position = Point2::new(400., 300.);
+        let mut sun = body(-1, sun_position.x, sun_position.y, SUN_SIZE);
+        sun.sun = true;
+        let mut bodies = vec![
+            sun,
+            body(0, 500., 300., 1.),
+            body(1, 400., 150., 1.),
+            body(2, 200., 300., 1.),
+        ];
+        for body in bodies.iter_mut().filter(|body| !body.sun) {
+            body.velocity =
+                circular_orbit_velocity(&body.position, body.mass, &sun_position, SUN_SIZE);
+        }
+        let initial_radii = bodies
+            .iter()
+            .map(|body| (body.position - sun_position).magnitude())
+            .collect::<Vec<_>>();
+
+        for _ in 0..5000 {
+            bodies = do_one_physics_step(0.005, bodies, &Config::default());
+            assert_eq!(bodies.len(), 4);
+            assert!(bodies.iter().all(|body| !body.delete));
+            for (body, initial_radius) in bodies.iter().zip(&initial_radii).skip(1) {
+                let radius = (body.position - sun_position).magnitude();
+                assert!(
+                    (radius - initial_radius).abs() < initial_radius * 0.02,
+                    "body {} drifted from radius {} to {}",
+                    body.id,
+                    initial_radius,
+                    radius
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn shallow_overlap_does_not_merge() {
+        let config = Config {
+            merge_overlap_fraction: 0.5,
+            ..Config::default()
+        };
+        let big = body(0, 0., 0., 100.);
+        let small_radius = Dimensions::from_mass(10., RadiusModel::Sphere).radius;
+        let touching_distance = big.radius + small_radius;
+
+        let shallow = body(1, touching_distance - small_radius * 0.25, 0., 10.);
+        let bodies = do_one_physics_step(0., vec![big.clone(), shallow], &config);
+        assert!(bodies.iter().all(|body| !body.delete));
+        assert_eq!(bodies[0].mass, 100.);
+
+        let deep = body(1, touching_distance - small_radius * 0.75, 0., 10.);
+        let bodies = do_one_physics_step(0., vec![big, deep], &config);
+        assert!(!bodies[0].delete);
+        assert!(bodies[1].delete);
+        assert_eq!(bodies[0].mass, 110.);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn merges_keep_the_mass_budget() {
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let before = vec![
+            sun.clone(),
+            body(0, 100., 0., 100.),
+            body(1, 102., 0., 10.),
+            body(2, sun.radius, 0., 5.),
+        ];
+        let after = do_one_physics_step(0., before.clone(), &Config::default());
+        assert!(after[2].delete);
+        assert!(after[3].delete && after[3].absorbed_by == Some(-1));
+
+        assert_mass_conserved(&before, &after);
+    }
+
+    #[test]
+    fn merges_conserve_momentum() {
+        let mut heavy = body(0, 100., 100., 100.);
+        heavy.velocity = Vector2::new(3., -1.);
+        let mut light = body(1, 102., 100., 10.);
+        light.velocity = Vector2::new(-20., 4.);
+        let mut lighter = body(2, 100., 102., 5.);
+        lighter.velocity = Vector2::new(7., 9.);
+        let before = vec![heavy, light, lighter];
+        let momentum = |bodies: &[Body]| {
+            bodies
+                .iter()
+                .filter(|body| !body.delete)
+                .map(|body| body.velocity * body.mass)
+                .sum::<Vector2<f64>>()
+        };
+
+        let after = do_one_physics_step(0., before.clone(), &Config::default());
+
+        assert!(after[1].delete && after[2].delete);
+        assert_eq!(after[0].mass, 115.);
+        assert!((momentum(&after) - momentum(&before)).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn merge_chains_end_up_in_the_last_survivor() {
+        // a touches b and b touches c, but a and c are too far apart to touch
+        let mut a = body(0, 100., 100., 2.);
+        a.velocity = Vector2::new(5., 0.);
+        let mut b = body(1, 101.5, 100., 3.);
+        b.velocity = Vector2::new(0., -2.);
+        let c = body(2, 103., 100., 5.);
+        let before = vec![a, b, c]
+            .into_iter()
+            .map(|mut body| {
+                body.radius = 1.;
+                body
+            })
+            .collect::<Vec<_>>();
+
+        let after = do_one_physics_step(0., before.clone(), &Config::default());
+
+        assert!(after[0].delete && after[0].absorbed_by == Some(2));
+        assert!(after[1].delete && after[1].absorbed_by == Some(2));
+        assert!(!after[2].delete);
+        assert_eq!(after[2].mass, 10.);
+        assert!((after[2].velocity - Vector2::new(1., -0.6)).magnitude() < 1e-12);
+        assert_mass_conserved(&before, &after);
+    }
+
+    #[test]
+    fn merges_can_conserve_angular_momentum_about_the_sun() {
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        let mut inner = body(0, 500., 300., 10.);
+        inner.velocity = Vector2::new(1., 20.);
+        let mut outer = body(1, 502., 300., 4.);
+        outer.velocity = Vector2::new(-3., 30.);
+        let before = vec![sun.clone(), inner, outer];
+        let total = |bodies: &[Body]| {
+            bodies
+                .iter()
+                .filter(|body| !body.sun && !body.delete)
+                .map(|body| angular_momentum_about(body, &sun))
+                .sum::<f64>()
+        };
+        let merge = |conserve_angular_momentum| {
+            let config = Config {
+                conserve_angular_momentum,
+                ..Config::default()
+            };
+            do_one_physics_step(0., before.clone(), &config)
+        };
+
+        // the survivor keeps its own, smaller, distance from the sun
+        let averaged = merge(false);
+        assert!(averaged[2].delete);
+        assert!((total(&averaged) - total(&before)).abs() > 1.);
+
+        let conserved = merge(true);
+        assert!(conserved[2].delete);
+        assert_eq!(conserved[1].mass, 14.);
+        assert!((total(&conserved) - total(&before)).abs() < 1e-9 * total(&before));
+        // only the speed along the orbit changes
+        assert_eq!(conserved[1].velocity.x, averaged[1].velocity.x);
+    }
+
+    #[test]
+    fn slow_bodies_brushing_the_sun_are_captured() {
+        let config = Config {
+            sun_capture: Some(SunCapture {
+                speed: 20.,
+                damping: 0.5,
+            }),
+            ..Config::default()
+        };
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let touching = sun.radius + body(0, 0., 0., 1.).radius - 0.5;
+        let mut grazing = body(0, touching, 0., 1.);
+        grazing.velocity = Vector2::new(0., 15.);
+        let mut diving = body(1, -touching, 0., 1.);
+        diving.velocity = Vector2::new(100., 0.);
+
+        let after = do_one_physics_step(0.001, vec![sun.clone(), grazing, diving], &config);
+
+        let captured = &after[1];
+        assert!(!captured.delete);
+        assert!(captured.velocity.magnitude() < 15.);
+        let offset = captured.position - sun.position;
+        assert!(captured.velocity.dot(&offset) > -1e-9);
+        let distance = offset.magnitude();
+        assert!((distance - sun.radius - captured.radius).abs() < 1e-9);
+        assert!(after[2].delete && after[2].absorbed_by == Some(-1));
+    }
+
+    #[test]
+    fn compact_sun_keeps_its_size_but_not_its_mass() {
+        let config = Config {
+            compact_sun_mass: Some(SUN_SIZE * 1.5),
+            ..Config::default()
+        };
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let swallow = |sun: Body, mass: f64| {
+            let falling = body(0, sun.radius, 0., mass);
+            let before = vec![sun, falling];
+            let after = do_one_physics_step(0., before.clone(), &config);
+            assert!(after[1].delete);
+            #[cfg(debug_assertions)]
+            assert_mass_conserved(&before, &after);
+            after[0].clone()
+        };
+
+        let grown = swallow(sun.clone(), SUN_SIZE * 0.25);
+        assert_eq!(grown.mass, SUN_SIZE * 1.25);
+        assert!(grown.radius > sun.radius);
+
+        let compact = swallow(grown.clone(), SUN_SIZE * 0.5);
+        assert_eq!(compact.mass, SUN_SIZE * 1.75);
+        let compact_radius = Dimensions::from_mass(SUN_SIZE * 1.5, RadiusModel::Sphere).radius;
+        assert_eq!(compact.radius, compact_radius);
+
+        let heavier = swallow(compact, SUN_SIZE * 0.5);
+        assert_eq!(heavier.mass, SUN_SIZE * 2.25);
+        assert_eq!(heavier.radius, compact_radius);
+
+        let unchanged = do_one_physics_step(
+            0.,
+            vec![sun.clone(), body(0, sun.radius, 0., 5.)],
+            &Config::default(),
+        );
+        assert_eq!(unchanged[0].mass, SUN_SIZE);
+        assert_eq!(unchanged[0].radius, sun.radius);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "mass budget drifted")]
+    fn losing_mass_fails_the_budget() {
+        let before = vec![body(0, 0., 0., 100.), body(1, 2., 0., 10.)];
+        let mut after = before.clone();
+        after[1].delete = true;
+
+        assert_mass_conserved(&before, &after);
+    }
+
+    #[test]
+    fn label_disappears_with_absorbed_body() {
+        let mut core = Core::new(Config {
+            labels: true,
+            ..Config::default()
+        });
+        spawn(&mut core, &body(0, 100., 100., 100.));
+        spawn(&mut core, &body(1, 101., 100., 10.));
+        let labels = |core: &Core| {
+            core.draw()
+                .drawables
+                .into_iter()
+                .filter_map(|drawable| drawable.label)
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&core), vec!["0", "1"]);
+
+        core.tick(0.005);
+
+        assert_eq!(labels(&core), vec!["0"]);
+    }
+
+    #[test]
+    fn slow_contacts_bounce_and_fast_impacts_merge() {
+        let config = Config {
+            collision_mode: CollisionMode::Bounce {
+                restitution: RestitutionCurve {
+                    slow: (10., 1.),
+                    fast: (100., 0.),
+                },
+            },
+            ..Config::default()
+        };
+        let approaching = |speed: f64| {
+            let mut left = body(0, 0., 0., 10.);
+            left.velocity = Vector2::new(speed, 0.);
+            let mut right = body(1, left.radius * 1.5, 0., 10.);
+            right.velocity = Vector2::new(-speed, 0.);
+            vec![left, right]
+        };
+
+        let bounced = do_one_physics_step(0., approaching(1.), &config);
+        assert!(bounced.iter().all(|body| !body.delete));
+        assert!((bounced[0].velocity.x + 1.).abs() < 1e-9);
+        assert!((bounced[1].velocity.x - 1.).abs() < 1e-9);
+
+        let merged = do_one_physics_step(0., approaching(100.), &config);
+        assert!(!merged[0].delete);
+        assert!(merged[1].delete);
+        assert_eq!(merged[0].mass, 20.);
+    }
+
+    #[test]
+    fn sticky_bodies_take_the_bounce_out_of_a_collision() {
+        let config = Config {
+            collision_mode: CollisionMode::Bounce {
+                restitution: RestitutionCurve {
+                    slow: (0., 0.9),
+                    fast: (100., 0.9),
+                },
+            },
+            ..Config::default()
+        };
+        let rebound = |restitution: f64| {
+            let mut left = body(0, 0., 0., 10.);
+            left.velocity = Vector2::new(2., 0.);
+            let mut right = body(1, left.radius * 1.5, 0., 10.);
+            right.velocity = Vector2::new(-2., 0.);
+            right.restitution = restitution;
+            let bounced = do_one_physics_step(0., vec![left, right], &config);
+            assert!(bounced.iter().all(|body| !body.delete));
+            bounced[1].velocity.x
+        };
+
+        let bouncy = rebound(1.);
+        let sticky = rebound(0.2);
+        assert!((bouncy - 0.9 * 2.).abs() < 1e-9);
+        assert!((sticky - 0.9 * 0.2 * 2.).abs() < 1e-9);
+        assert!(bouncy > sticky);
+    }
+
+    #[test]
+    fn cloud_without_a_sun_collapses() {
+        let mut core = Core::new(Config {
+            seed: Some(5),
+            num_bodies: 40,
+            no_sun: true,
+            initial_velocity: InitialVelocity::Random { speed: 0. },
+            ..Config::default()
+        });
+        core.init();
+        let bodies = get_bodies(&core.world);
+        assert!(!bodies.is_empty());
+        assert!(bodies.iter().all(|body| !body.sun));
+        assert_eq!(bodies.len(), core.body_count());
+
+        let spread = |core: &Core| {
+            let bodies = get_bodies(&core.world);
+            let center = core.diagnostics().barycenter;
+            bodies
+                .iter()
+                .map(|body| (body.position - center).magnitude_squared() * body.mass)
+                .sum::<f64>()
+                / bodies.iter().map(|body| body.mass).sum::<f64>()
+        };
+        let before = spread(&core);
+        for _ in 0..100 {
+            core.tick(0.01);
+        }
+        assert!(spread(&core) < before);
+    }
+
+    #[test]
+    fn velocity_bias_shears_the_initial_velocities() {
+        let mut core = Core::new(Config {
+            seed: Some(3),
+            num_bodies: 50,
+            initial_velocity: InitialVelocity::Random { speed: 0. },
+            velocity_bias: Some(VelocityBias {
+                x: Linear {
+                    constant: 2.,
+                    x: 0.,
+                    y: 0.25,
+                },
+                y: Linear {
+                    constant: 0.,
+                    x: 0.,
+                    y: 0.,
+                },
+            }),
+            ..Config::default()
+        });
+        core.init();
+        let sun = screen_center();
+
+        let bodies = get_bodies(&core.world);
+        assert!(bodies.len() > 40);
+        for body in bodies.iter().filter(|body| !body.sun) {
+            let expected = 2. + 0.25 * (body.position.y - sun.y);
+            assert!((body.velocity.x - expected).abs() < 1e-9);
+            assert_eq!(body.velocity.y, 0.);
+        }
+    }
+
+    #[test]
+    fn snapshot_diff_reports_changed_bodies() {
+        let mut core = Core::new(Config::default());
+        core.init();
+        let snapshot = core.snapshot();
+        assert_eq!(
+            snapshot.lines().count(),
+            core.config.num_bodies as usize + 1
+        );
+        assert_eq!(
+            diff_snapshots(&snapshot, &core.snapshot()),
+            Vec::<i32>::new()
+        );
+
+        <(Read<Id>, Write<Velocity>)>::query().for_each_mut(
+            &mut core.world,
+            |(id, mut velocity)| {
+                if id.id == 2 {
+                    velocity.vector.x += 1e-9;
+                }
+            },
+        );
+        assert_eq!(diff_snapshots(&snapshot, &core.snapshot()), vec![2]);
+
+        spawn(&mut core, &body(100, 0., 0., 1.));
+        assert_eq!(diff_snapshots(&snapshot, &core.snapshot()), vec![2, 100]);
+    }
+
+    #[test]
+    fn gravity_cutoff_ignores_distant_bodies() {
+        let config = Config {
+            gravity_cutoff: Some(50.),
+            ..Config::default()
+        };
+        let bodies = vec![
+            body(0, 0., 0., 10.),
+            body(1, 60., 0., 10.),
+            body(2, 200., 30., 10.),
+        ];
+
+        let bodies = do_one_physics_step(0.1, bodies, &config);
+
+        assert_eq!(bodies[1].velocity, Vector2::new(0., 0.));
+        assert_eq!(bodies[2].velocity, Vector2::new(0., 0.));
+        assert_eq!(bodies[0].velocity, Vector2::new(0., 0.));
+
+        let config = Config {
+            gravity_cutoff: Some(100.),
+            ..config
+        };
+        let bodies = do_one_physics_step(0.1, bodies, &config);
+        assert!(bodies[0].velocity.x > 0.);
+        assert!(bodies[1].velocity.x < 0.);
+        assert_eq!(bodies[2].velocity, Vector2::new(0., 0.));
+    }
+
+    #[test]
+    fn acceleration_clamp_only_limits_violent_pulls() {
+        let bodies = vec![
+            body(0, 0., 0., 1000.),
+            body(1, 20., 0., 1.),
+            body(2, 0., 2000., 1.),
+        ];
+        let time_step = 0.001;
+        let accelerations = |bodies: Vec<Body>| {
+            bodies
+                .into_iter()
+                .map(|body| body.velocity / time_step)
+                .collect::<Vec<_>>()
+        };
+        let free = accelerations(do_one_physics_step(
+            time_step,
+            bodies.clone(),
+            &Config::default(),
+        ));
+        let max_acceleration = free[1].magnitude() / 2.;
+        assert!(free[2].magnitude() < max_acceleration);
+
+        let config = Config {
+            max_acceleration: Some(max_acceleration),
+            ..Config::default()
+        };
+        let clamped = accelerations(do_one_physics_step(time_step, bodies, &config));
+
+        assert!((clamped[1].magnitude() - max_acceleration).abs() < 1e-9 * max_acceleration);
+        assert!((clamped[1].normalize() - free[1].normalize()).magnitude() < 1e-12);
+        assert_eq!(clamped[2], free[2]);
+    }
+
+    #[test]
+    fn tree_takes_over_from_the_threshold() {
+        assert_eq!(gravity_method(1000, None), GravityMethod::Exact);
+        assert_eq!(gravity_method(99, Some(100)), GravityMethod::Exact);
+        assert_eq!(gravity_method(100, Some(100)), GravityMethod::Tree);
+        assert_eq!(gravity_method(101, Some(100)), GravityMethod::Tree);
+    }
+
+    #[test]
+    fn tree_and_exact_gravity_agree_at_the_crossover() {
+        let mut rng = seeded_rng(Some(9));
+        let bodies = (0..100)
+            .map(|id| {
+                body(
+                    id,
+                    rng.gen_range(0., 800.),
+                    rng.gen_range(0., 600.),
+                    rng.gen_range(1., 10.),
+                )
+            })
+            .collect::<Vec<_>>();
+        // no time passes, so the velocities are just what gravity adds per unit of time
+        let accelerations = |threshold: usize| {
+            let config = Config {
+                tree_gravity_threshold: Some(threshold),
+                ..Config::default()
+            };
+            do_one_physics_step(1e-9, bodies.clone(), &config)
+                .into_iter()
+                .map(|body| body.velocity / 1e-9)
+                .collect::<Vec<_>>()
+        };
+        let exact = accelerations(101);
+        let tree = accelerations(100);
+
+        let largest = exact.iter().map(|a| a.magnitude()).fold(0., f64::max);
+        assert_ne!(exact, tree);
+        for (exact, tree) in exact.iter().zip(&tree) {
+            assert!((exact - tree).magnitude() < 0.01 * largest);
+        }
+    }
+
+    #[test]
+    fn collision_pairs_list_exactly_the_overlapping_bodies() {
+        let mut core = Core::new(Config {
+            collision_mode: CollisionMode::Bounce {
+                restitution: RestitutionCurve {
+                    slow: (0., 1.),
+                    fast: (1., 1.),
+                },
+            },
+            ..Config::default()
+        });
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 102., 100., 10.));
+        spawn(&mut core, &body(2, 300., 300., 10.));
+        spawn(&mut core, &body(3, 302., 301., 10.));
+        spawn(&mut core, &body(4, 500., 100., 10.));
+
+        core.tick(0.001);
+        assert_eq!(core.collision_pairs(), &[(0, 1), (2, 3)]);
+
+        core.world.delete_all();
+        spawn(&mut core, &body(4, 500., 100., 10.));
+        core.tick(0.001);
+        assert!(core.collision_pairs().is_empty());
+    }
+
+    #[test]
+    fn contact_margin_counts_near_misses_as_collisions() {
+        let radius = Dimensions::from_mass(10., RadiusModel::Sphere).radius;
+        // half a unit apart, on either side of a grid cell edge
+        let bodies = vec![
+            body(0, 20. - radius - 0.26, 100., 10.),
+            body(1, 20. + radius + 0.24, 100., 10.),
+        ];
+        let (left, right) = (&bodies[0], &bodies[1]);
+
+        assert!(!are_colliding(
+            left.position,
+            left.radius,
+            right.position,
+            right.radius,
+            0.
+        ));
+        assert!(are_colliding(
+            left.position,
+            left.radius,
+            right.position,
+            right.radius,
+            1.
+        ));
+        assert!(!are_colliding(
+            left.position,
+            left.radius,
+            right.position,
+            right.radius,
+            0.4
+        ));
+        assert!(detect_collisions(&bodies, 20., 0.).is_empty());
+        assert_eq!(detect_collisions(&bodies, 20., 1.), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn head_on_impacts_are_recorded_where_the_surfaces_meet() {
+        let mut core = Core::new(Config::default());
+        let mut big = body(0, 100., 100., 8.);
+        big.velocity = Vector2::new(3., 0.);
+        let mut small = body(1, 100. + big.radius, 100., 1.);
+        small.velocity = Vector2::new(-4., 0.);
+        spawn(&mut core, &big);
+        spawn(&mut core, &small);
+        core.tick(0.);
+
+        let impacts = &core.impacts;
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].pair, (0, 1));
+        assert_eq!(impacts[0].speed, 7.);
+        // twice the radius, so two thirds of the way over
+        assert!((big.radius - 2. * small.radius).abs() < 1e-9);
+        let expected = Point2::new(100. + big.radius * 2. / 3., 100.);
+        assert!((impacts[0].point - expected).magnitude() < 1e-9);
+        assert_eq!(core.collision_pairs(), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn pairs_sharing_several_cells_collide_once() {
+        // both are far bigger than the cells, and they overlap across a corner of the grid
+        let left = body(0, 49.5, 50., 400.);
+        let right = body(1, 50.5, 50., 300.);
+        assert!(left.radius > 1. && right.radius > 1.);
+        let bodies = vec![left, right];
+        let shared_cells = build_broadphase(1., 0., &bodies)
+            .occupancy()
+            .values()
+            .filter(|&&occupancy| occupancy == 2)
+            .count();
+        assert!(shared_cells > 1);
+
+        assert_eq!(detect_collisions(&bodies, 1., 0.), vec![(0, 1)]);
+        let config = Config {
+            broadphase_cell_size: 1.,
+            ..Config::default()
+        };
+        let (after, impacts) = physics_step(0.001, bodies, &config, GRAVITATIONAL_CONSTANT);
+        assert_eq!(impacts.len(), 1);
+        assert_eq!(impacts[0].pair, (0, 1));
+        assert_eq!(after.iter().filter(|body| body.delete).count(), 1);
+        assert_eq!(after[0].mass, 700.);
+    }
+
+    #[test]
+    fn full_accretion_nudge_leaves_merged_bodies_on_circular_orbits() {
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        let mut falling = body(0, 500., 300., 4.);
+        falling.velocity = Vector2::new(-3., 7.);
+        let mut other = body(1, 500.5, 300., 2.);
+        other.velocity = Vector2::new(5., 1.);
+        let mut clockwise = body(2, 400., 200., 4.);
+        clockwise.velocity = Vector2::new(-2., 0.);
+        let mut clockwise_other = body(3, 400.5, 200., 2.);
+        clockwise_other.velocity = Vector2::new(0., 1.);
+        let config = Config {
+            accretion_disk: Some(AccretionDisk {
+                radius: 150.,
+                nudge: 1.,
+            }),
+            ..Config::default()
+        };
+
+        let bodies = do_one_physics_step(
+            0.,
+            vec![sun.clone(), falling, other, clockwise, clockwise_other],
+            &config,
+        );
+
+        let survivor = bodies.iter().find(|body| body.id == 0).unwrap();
+        assert_eq!(survivor.mass, 6.);
+        let elements = orbital_elements(
+            survivor.position - sun.position,
+            survivor.velocity,
+            GRAVITATIONAL_CONSTANT * survivor.mass * SUN_SIZE,
+        )
+        .unwrap();
+        assert!(elements.eccentricity < 1e-9);
+        assert!(survivor.velocity.y > 0.);
+        let survivor = bodies.iter().find(|body| body.id == 2).unwrap();
+        let circular_speed = (GRAVITATIONAL_CONSTANT * 6. * SUN_SIZE / 100.).sqrt();
+        assert!((survivor.velocity - Vector2::new(-circular_speed, 0.)).magnitude() < 1e-9);
+
+        // too far out to be nudged
+        let mut far = body(0, 700., 300., 4.);
+        far.velocity = Vector2::new(-3., 7.);
+        let mut other = body(1, 700.5, 300., 2.);
+        other.velocity = Vector2::new(5., 1.);
+        let bodies = do_one_physics_step(0., vec![sun, far, other], &config);
+        let survivor = bodies.iter().find(|body| body.id == 0).unwrap();
+        assert_eq!(
+            survivor.velocity,
+            Vector2::new(-3. * 4. + 5. * 2., 7. * 4. + 2.) / 6.
+        );
+    }
+
+    #[test]
+    fn incremental_prediction_matches_the_one_shot_path() {
+        let mut core = Core::new(Config {
+            seed: Some(4),
+            num_bodies: 20,
+            ..Config::default()
+        });
+        core.init();
+        let selected = get_bodies(&core.world)[5].position;
+        core.click(selected.coords, 1., false);
+
+        let mut one_shot = Prediction::new(0.05, &core.world, &core.config);
+        one_shot.extend(&core.config, None);
+        assert!(one_shot.is_complete());
+
+        let mut incremental = Prediction::new(0.05, &core.world, &core.config);
+        let mut calls = 0;
+        while !incremental.is_complete() {
+            // no time to spare at all, so every call only gets one point further
+            incremental.extend(&core.config, Some(Instant::now()));
+            calls += 1;
+            if !incremental.is_complete() {
+                assert_ne!(incremental.time_to_impact(), Some(TimeToImpact::Stable));
+            }
+        }
+
+        assert!(calls > 1);
+        assert_eq!(incremental.path, one_shot.path);
+        assert!(!one_shot.path.is_empty());
+        assert_eq!(incremental.time_to_impact(), one_shot.time_to_impact());
+    }
+
+    #[test]
+    fn predicted_orbit_around_the_sun_closes() {
+        let config = Config {
+            prediction_steps: 2001,
+            prediction_sample_interval: NonZeroUsize::new(50).unwrap(),
+            ..Config::default()
+        };
+        let mut core = Core::new(config.clone());
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let mut orbiting = body(0, 100., 0., 1.);
+        orbiting.velocity =
+            circular_orbit_velocity(&orbiting.position, 1., &sun.position, SUN_SIZE);
+        orbiting.selected = true;
+        spawn(&mut core, &sun);
+        spawn(&mut core, &orbiting);
+        // the first point is a step in, so the last is a whole orbit after it
+        let period = 2. * PI * 100. / orbiting.velocity.magnitude();
+
+        let mut prediction = Prediction::new(period / 2000., &core.world, &config);
+        prediction.extend(&config, None);
+
+        assert!(prediction.is_complete());
+        assert_eq!(prediction.path.len(), 41);
+        let (first, last) = (prediction.path[0], prediction.path[40]);
+        assert!((last - first).magnitude() < 1., "{} -> {}", first, last);
+        // half way round, on the other side of the sun
+        assert!((prediction.path[20] + first.coords).coords.magnitude() < 1.);
+        assert_eq!(prediction.time_to_impact(), Some(TimeToImpact::Stable));
+    }
+
+    #[test]
+    fn predicting_with_only_the_heavy_bodies_stays_close() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        let mut orbiting = body(0, 500., 300., 1.);
+        orbiting.velocity =
+            circular_orbit_velocity(&orbiting.position, 1., &sun.position, SUN_SIZE);
+        orbiting.selected = true;
+        spawn(&mut core, &orbiting);
+        for i in 1..=40 {
+            let angle = i as f64 * 0.157;
+            spawn(
+                &mut core,
+                &body(
+                    i,
+                    400. + 300. * angle.cos(),
+                    300. + 300. * angle.sin(),
+                    0.01,
+                ),
+            );
+        }
+        let predict = |limit: Option<usize>| {
+            let config = Config {
+                prediction_body_limit: limit,
+                prediction_steps: 2000,
+                ..Config::default()
+            };
+            let mut prediction = Prediction::new(0.01, &core.world, &config);
+            assert_eq!(prediction.bodies.len(), limit.map_or(42, |limit| limit + 1));
+            prediction.extend(&config, None);
+            prediction.path
+        };
+
+        let full = predict(None);
+        let reduced = predict(Some(1));
+
+        assert_eq!(full.len(), reduced.len());
+        for (full, reduced) in full.iter().zip(&reduced) {
+            assert!(
+                (full - reduced).magnitude() < 0.5,
+                "{} vs {}",
+                full,
+                reduced
+            );
+        }
+    }
+
+    #[test]
+    fn predicts_time_to_fall_into_the_sun() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let mut falling = body(0, 100., 0., 1.);
+        falling.selected = true;
+        spawn(&mut core, &sun);
+        spawn(&mut core, &falling);
+
+        core.pause();
+        core.tick(0.005);
+
+        // radial free fall from rest until the surfaces touch
+        let mu = GRAVITATIONAL_CONSTANT * falling.mass * sun.mass;
+        let start: f64 = 100.;
+        let ratio = (sun.radius + falling.radius) / start;
+        let expected = (start.powi(3) / (2. * mu)).sqrt()
+            * ((ratio * (1. - ratio)).sqrt() + ratio.sqrt().acos());
+        match core.draw().time_to_impact {
+            Some(TimeToImpact::Seconds(seconds)) => assert!(
+                (seconds - expected).abs() < expected * 0.01,
+                "expected impact after {}s, predicted {}s",
+                expected,
+                seconds
+            ),
+            other => panic!("expected an impact, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn circular_orbit_is_stable() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let mut orbiting = body(0, 100., 0., 1.);
+        orbiting.selected = true;
+        orbiting.velocity =
+            circular_orbit_velocity(&orbiting.position, 1., &sun.position, SUN_SIZE);
+        spawn(&mut core, &sun);
+        spawn(&mut core, &orbiting);
+
+        core.pause();
+        core.tick(0.005);
+
+        assert_eq!(core.draw().time_to_impact, Some(TimeToImpact::Stable));
+    }
+
+    #[test]
+    fn start_paused_waits_for_unpause() {
+        let mut core = Core::new(Config {
+            start_paused: true,
+            ..Config::default()
+        });
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 300., 100., 10.));
+        let initial = core.snapshot();
+
+        for _ in 0..5 {
+            core.tick(0.005);
+        }
+        assert_eq!(core.tick_count(), TickCount(0));
+        assert_eq!(core.snapshot(), initial);
+
+        // editing works while paused
+        core.click(Vector2::new(300., 100.), 1., false);
+        core.delete_selected();
+        assert_eq!(core.snapshot().lines().count(), 1);
+
+        core.pause();
+        core.tick(0.005);
+        assert_eq!(core.tick_count(), TickCount(1));
+    }
+
+    #[test]
+    fn tight_pair_is_bound_and_diverging_pair_is_not() {
+        let mut left = body(0, 0., 0., 10.);
+        let mut right = body(1, 10., 0., 10.);
+        left.velocity = Vector2::new(0., 1.);
+        right.velocity = Vector2::new(0., -1.);
+        assert!(total_energy(&[left.clone(), right.clone()], 1) < 0.);
+
+        left.velocity = Vector2::new(-100., 0.);
+        right.velocity = Vector2::new(100., 0.);
+        assert!(total_energy(&[left, right], 1) > 0.);
+    }
+
+    #[test]
+    fn binding_energy_is_shown_for_selected_groups() {
+        let mut core = Core::new(Config::default());
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 110., 100., 10.));
+        spawn(&mut core, &body(2, 300., 100., 10.));
+
+        core.click(Vector2::new(100., 100.), 1., false);
+        assert_eq!(core.draw().group, None);
+
+        core.click(Vector2::new(110., 100.), 1., true);
+        let group = core.draw().group.unwrap();
+        assert_eq!(group.energy, -GRAVITATIONAL_CONSTANT * 10. * 10. / 10.);
+        assert_eq!(group.barycenter, Point2::new(105., 100.));
+    }
+
+    #[test]
+    fn clicks_pick_bodies_within_a_zoomed_tolerance_of_their_edge() {
+        let mut core = Core::new(Config::default());
+        let big = body(0, 200., 200., 8000.);
+        // radius of about 12
+        assert!(big.radius > 10.);
+        spawn(&mut core, &big);
+        let selected = |core: &Core| get_bodies(&core.world)[0].selected;
+
+        core.click(Vector2::new(209., 200.), 1., false);
+        assert!(selected(&core));
+
+        // 4 away from the edge is 8 pixels when zoomed in twice, 2 when zoomed out
+        let near_edge = Vector2::new(200. + big.radius + 4., 200.);
+        core.click(near_edge, 2., false);
+        assert!(!selected(&core));
+        core.click(near_edge, 0.5, false);
+        assert!(selected(&core));
+    }
+
+    #[test]
+    fn sun_heats_up_with_what_it_swallows() {
+        let heating = SunHeating {
+            full_at: 200.,
+            color: Rgb([255, 255, 255]),
+        };
+        assert_eq!(heated_sun_color(SUN_COLOR, &heating, 0.), [255, 255, 0]);
+        assert_eq!(heated_sun_color(SUN_COLOR, &heating, 50.), [255, 255, 64]);
+        assert_eq!(heated_sun_color(SUN_COLOR, &heating, 200.), [255, 255, 255]);
+        assert_eq!(
+            heated_sun_color(SUN_COLOR, &heating, 1000.),
+            [255, 255, 255]
+        );
+        assert_eq!(
+            heated_sun_color([200, 100, 0], &heating, 100.),
+            [228, 178, 128]
+        );
+    }
+
+    #[test]
+    fn accretion_heats_the_drawn_sun() {
+        let mut core = Core::new(Config {
+            sun_heating: Some(SunHeating {
+                full_at: 10.,
+                color: Rgb([255, 255, 255]),
+            }),
+            ..Config::default()
+        });
+        let mut sun = body(0, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        spawn(&mut core, &body(1, 402., 300., 5.));
+        let sun_color = |core: &Core| {
+            core.draw()
+                .drawables
+                .into_iter()
+                .find(|drawable| drawable.sun)
+                .and_then(|drawable| drawable.color)
+        };
+        assert_eq!(sun_color(&core), Some([255, 255, 0]));
+
+        core.tick(0.001);
+        assert_eq!(core.accreted_mass, 5.);
+        assert_eq!(sun_color(&core), Some([255, 255, 128]));
+    }
+
+    #[test]
+    fn relative_motion_is_positive_while_closing_in() {
+        let position = Point2::new(0., 0.);
+        let other_position = Point2::new(10., 0.);
+        let approaching = relative_motion(
+            &position,
+            &Vector2::new(3., 0.),
+            &other_position,
+            &Vector2::new(-1., 5.),
+        );
+        assert_eq!(approaching.velocity, Vector2::new(-4., 5.));
+        assert_eq!(approaching.closing_speed, 4.);
+
+        let leaving = relative_motion(
+            &other_position,
+            &Vector2::new(-1., 5.),
+            &position,
+            &Vector2::new(-3., 0.),
+        );
+        assert_eq!(leaving.closing_speed, -2.);
+    }
+
+    #[test]
+    fn measuring_between_a_body_and_a_spot() {
+        let mut core = Core::new(Config::default());
+        let mut moving = body(0, 200., 200., 1.);
+        moving.velocity = Vector2::new(0., 7.);
+        spawn(&mut core, &moving);
+
+        core.measure(Vector2::new(200., 200.), 1.);
+        assert_eq!(core.draw().measurement, None);
+        core.measure(Vector2::new(200., 300.), 1.);
+        let measurement = core.draw().measurement.unwrap();
+        assert_eq!(measurement.distance, 100.);
+        // a spot doesn't move, so there's nothing to compare with
+        assert_eq!(measurement.motion, None);
+
+        let mut other = body(1, 260., 200., 1.);
+        other.velocity = Vector2::new(-5., 0.);
+        spawn(&mut core, &other);
+        core.measure(Vector2::new(200., 200.), 1.);
+        core.measure(Vector2::new(260., 200.), 1.);
+        let measurement = core.draw().measurement.unwrap();
+        assert_eq!(measurement.distance, 60.);
+        assert_eq!(measurement.motion.unwrap().closing_speed, 5.);
+    }
+
+    #[test]
+    fn deterministic_sum_ignores_the_order_bodies_are_stored_in() {
+        let config = Config {
+            deterministic_sum: true,
+            ..Config::default()
+        };
+        let mut rng = StdRng::seed_from_u64(11);
+        let bodies = (0..30)
+            .map(|id| {
+                body(
+                    id,
+                    rng.gen_range(0., 200.),
+                    rng.gen_range(0., 200.),
+                    rng.gen_range(1., 5.),
+                )
+            })
+            .collect::<Vec<_>>();
+        let run = |bodies: Vec<Body>| {
+            let mut bodies = (0..50).fold(bodies, |bodies, _| {
+                do_one_physics_step(0.1, bodies, &config)
+            });
+            bodies.sort_by_key(|body| body.id);
+            bodies
+                .iter()
+                .map(|body| {
+                    (
+                        body.position.x.to_bits(),
+                        body.position.y.to_bits(),
+                        body.velocity.x.to_bits(),
+                        body.velocity.y.to_bits(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let forward = run(bodies.clone());
+        let backward = run(bodies.into_iter().rev().collect());
+        assert_eq!(forward, backward);
+    }
+
+    #[test]
+    fn bodies_are_classified_by_where_they_are_headed() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        let mut circling = body(0, 600., 300., 1.);
+        circling.velocity =
+            circular_orbit_velocity(&circling.position, 1., &sun.position, SUN_SIZE);
+        let mut leaving = body(1, 400., 100., 1.);
+        leaving.velocity = Vector2::new(0., -1000.);
+        let mut diving = body(2, 100., 300., 1.);
+        diving.velocity = Vector2::new(20., 0.);
+        // not aimed right at it, but bound on an orbit that dips into the sun
+        let mut grazing = body(3, 400., 500., 1.);
+        grazing.velocity = Vector2::new(1., -3.);
+        for body in &[circling, leaving, diving, grazing] {
+            spawn(&mut core, body);
+        }
+
+        let mut fates = core.classify_bodies();
+        fates.sort_by_key(|(id, _)| *id);
+        assert_eq!(
+            fates,
+            vec![
+                (0, Fate::Bound),
+                (1, Fate::Escaping),
+                (2, Fate::Colliding),
+                (3, Fate::Colliding),
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_do_not_depend_on_thread_count() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let bodies = (0..500)
+            .map(|id| {
+                let mut body = body(
+                    id,
+                    rng.gen_range(0., 1000.),
+                    rng.gen_range(0., 1000.),
+                    rng.gen_range(1., 50.),
+                );
+                body.velocity = Vector2::new(rng.gen_range(-50., 50.), rng.gen_range(-50., 50.));
+                body
+            })
+            .collect::<Vec<_>>();
+
+        let serial = diagnostics(&bodies, 1);
+        for threads in &[2, 3, 8] {
+            let parallel = diagnostics(&bodies, *threads);
+            // exact, not approximately equal
+            assert_eq!(serial.energy.to_bits(), parallel.energy.to_bits());
+            assert_eq!(serial.momentum, parallel.momentum);
+            assert_eq!(serial.barycenter, parallel.barycenter);
+        }
+    }
+
+    #[test]
+    fn equal_mass_merge_keeps_the_lower_id() {
+        for repeat in 0..50 {
+            let mut left = body(3, 0., 0., 10.);
+            left.velocity = Vector2::new(5., 0.);
+            let mut right = body(7, left.radius, 0., 10.);
+            right.velocity = Vector2::new(-5., 0.);
+            let bodies = if repeat % 2 == 0 {
+                vec![left, right]
+            } else {
+                vec![right, left]
+            };
+
+            let survivors = do_one_physics_step(0.005, bodies, &Config::default())
+                .into_iter()
+                .filter(|body| !body.delete)
+                .collect::<Vec<_>>();
+
+            assert_eq!(survivors.len(), 1);
+            assert_eq!(survivors[0].id, 3);
+            assert_eq!(survivors[0].mass, 20.);
+        }
+    }
+
+    #[test]
+    fn samples_largest_mass_every_interval() {
+        let mut core = Core::new(Config::default());
+        core.toggle_mass_graph();
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        spawn(&mut core, &body(0, 300., 300., 10.));
+        spawn(&mut core, &body(1, 600., 300., 20.));
+        for _ in 0..GRAPH_SAMPLE_INTERVAL {
+            core.tick(0.005);
+        }
+        spawn(&mut core, &body(2, 600., 600., 30.));
+        for _ in 0..GRAPH_SAMPLE_INTERVAL * 2 {
+            core.tick(0.005);
+        }
+
+        let interval = GRAPH_SAMPLE_INTERVAL;
+        assert_eq!(
+            core.draw().largest_mass_history,
+            vec![(interval, 20.), (interval * 2, 30.), (interval * 3, 30.)]
+        );
+    }
+
+    #[test]
+    fn finds_the_two_heaviest_bodies() {
+        let mut sun = body(-1, 0., 0., SUN_SIZE);
+        sun.sun = true;
+        let bodies = vec![
+            body(0, 0., 0., 5.),
+            sun,
+            body(1, 0., 0., 40.),
+            body(2, 0., 0., 12.),
+            body(3, 0., 0., 40.),
+            body(4, 0., 0., 39.),
+        ];
+        let (first, second) = two_heaviest(&bodies).unwrap();
+        assert_eq!((first.id, second.id), (1, 3));
+        assert!(two_heaviest(&bodies[..2]).is_none());
+    }
+
+    #[test]
+    fn bodies_at_l4_stand_still_in_the_rotating_frame() {
+        let config = Config {
+            rotating_frame: true,
+            ..Config::default()
+        };
+        // equal masses, so l4 makes an equilateral triangle with them
+        let l4 = Point2::new(400., 300. - 200. * 3_f64.sqrt() / 2.);
+        let run = |start: Point2<f64>| {
+            let mut bodies = vec![
+                body(0, 300., 300., 1000.),
+                body(1, 500., 300., 1000.),
+                // far lighter than the pair, gravity and the frame both have to scale with it
+                body(2, start.x, start.y, 1.),
+            ];
+            for _ in 0..1000 {
+                bodies = do_one_physics_step(0.05, bodies, &config);
+            }
+            assert_eq!(bodies[0].position, Point2::new(300., 300.));
+            assert_eq!(bodies[1].position, Point2::new(500., 300.));
+            (bodies[2].position - start).magnitude()
+        };
+
+        assert!(run(l4) < 0.01);
+        assert!(run(l4 + Vector2::new(0., 20.)) > 1.);
+    }
+
+    #[test]
+    fn samples_separation_of_the_two_heaviest_bodies() {
+        let mut core = Core::new(Config::default());
+        core.toggle_separation_graph();
+        spawn(&mut core, &body(0, 100., 100., 30.));
+        spawn(&mut core, &body(1, 400., 500., 20.));
+        spawn(&mut core, &body(2, 700., 100., 1.));
+        for _ in 0..GRAPH_SAMPLE_INTERVAL {
+            core.tick(1e-9);
+        }
+        let samples = core.draw().separation_history;
+        assert_eq!(samples.len(), 1);
+        assert_eq!(samples[0].0, GRAPH_SAMPLE_INTERVAL);
+        assert!((samples[0].1 - 500.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn substeps_reduce_energy_drift() {
+        let max_energy_drift = |substeps: u32| {
+            let mut core = Core::new(Config {
+                substeps,
+                ..Config::default()
+            });
+            let mut sun = body(-1, 0., 0., SUN_SIZE);
+            sun.sun = true;
+            let mut orbiting = body(0, 100., 0., 1.);
+            orbiting.velocity =
+                circular_orbit_velocity(&orbiting.position, 1., &sun.position, SUN_SIZE) * 0.8;
+            spawn(&mut core, &sun);
+            spawn(&mut core, &orbiting);
+
+            let initial_energy = total_energy(&get_bodies(&core.world), 1);
+            (0..2000)
+                .map(|_| {
+                    core.tick(0.02);
+                    (total_energy(&get_bodies(&core.world), 1) - initial_energy).abs()
+                })
+                .fold(0., f64::max)
+        };
+
+        let single = max_energy_drift(1);
+        let quadruple = max_energy_drift(4);
+        assert!(
+            quadruple < single / 2.,
+            "4 substeps drifted {}, 1 substep drifted {}",
+            quadruple,
+            single
+        );
+    }
+
+    #[test]
+    fn logarithmic_potential_has_a_flat_rotation_curve() {
+        let circular_speed = 30.;
+        let config = Config {
+            background_potential: Some(BackgroundPotential::Logarithmic {
+                circular_speed,
+                core_radius: 0.1,
+            }),
+            ..Config::default()
+        };
+        let center = screen_center();
+        let radii = [40., 100., 250.];
+        let mut bodies = radii
+            .iter()
+            .enumerate()
+            .map(|(i, radius)| {
+                let angle = i as f64 * 2.;
+                let direction = Vector2::new(angle.cos(), angle.sin());
+                let mut body = body(i as i32, 0., 0., 0.001);
+                body.position = center + direction * *radius;
+                body.velocity = Vector2::new(-direction.y, direction.x) * circular_speed;
+                body
+            })
+            .collect::<Vec<_>>();
+
+        for _ in 0..4000 {
+            bodies = do_one_physics_step(0.005, bodies, &config);
+        }
+
+        for (body, radius) in bodies.iter().zip(&radii) {
+            let distance = (body.position - center).magnitude();
+            assert!(
+                (distance - radius).abs() < radius * 0.02,
+                "body at radius {} drifted to {}",
+                radius,
+                distance
+            );
+            assert!((body.velocity.magnitude() - circular_speed).abs() < circular_speed * 0.02);
+        }
+    }
+
+    #[test]
+    fn seeded_init_places_no_overlapping_bodies() {
+        let mut core = Core::new(Config {
+            seed: Some(7),
+            num_bodies: 500,
+            ..Config::default()
+        });
+        core.init();
+
+        let bodies = get_bodies(&core.world);
+        assert!(bodies.len() > 450);
+        for (body, other) in bodies.iter().tuple_combinations() {
+            assert!(
+                !are_colliding(body.position, body.radius, other.position, other.radius, 0.),
+                "{} and {} overlap",
+                body.id,
+                other.id
+            );
+        }
+
+        let mut again = Core::new(core.config.clone());
+        again.init();
+        assert_eq!(again.snapshot(), core.snapshot());
+    }
+
+    #[test]
+    fn merge_leaves_debris_when_enabled() {
+        let mut core = Core::new(Config {
+            debris: true,
+            ..Config::default()
+        });
+        let mut moving = body(0, 100., 100., 100.);
+        moving.velocity = Vector2::new(600., 0.);
+        spawn(&mut core, &moving);
+        spawn(&mut core, &body(1, 101., 100., 10.));
+
+        core.tick(0.005);
+
+        assert_eq!(get_bodies(&core.world).len(), 1);
+        let debris = <Read<Debris>>::query()
+            .iter(&core.world)
+            .map(|debris| *debris)
+            .collect::<Vec<_>>();
+        assert_eq!(debris.len(), 1);
+        assert_eq!(debris[0].parent, 0);
+
+        // it rides along with the survivor
+        let survivor = get_bodies(&core.world)[0].clone();
+        core.tick(0.005);
+        let survivor_after = get_bodies(&core.world)[0].clone();
+        let debris_position = <(Read<Position>, Read<Debris>)>::query()
+            .iter(&core.world)
+            .map(|(position, _)| position.point)
+            .next()
+            .unwrap();
+        assert!(survivor_after.position.x > survivor.position.x + 2.);
+        assert!((debris_position - survivor_after.position - debris[0].offset).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn static_bodies_stay_put_but_still_pull() {
+        let mut core = Core::new(Config::default());
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 200., 100., 10.));
+        let position_of = |core: &Core, id: i32| {
+            get_bodies(&core.world)
+                .into_iter()
+                .find(|body| body.id == id)
+                .unwrap()
+                .position
+        };
+
+        core.click(Vector2::new(100., 100.), 1., false);
+        core.toggle_static_selected();
+        for _ in 0..10 {
+            core.tick(0.01);
+        }
+        assert_eq!(position_of(&core, 0), Point2::new(100., 100.));
+        assert!(position_of(&core, 1).x < 200.);
+
+        core.toggle_static_selected();
+        core.tick(0.01);
+        assert!(position_of(&core, 0).x > 100.);
+    }
+
+    #[test]
+    fn circularize_snaps_the_selected_body_to_orbital_speed() {
+        let mut core = Core::new(Config::default());
+        core.spawn_sun(Point2::new(400., 300.), SUN_SIZE, None);
+        spawn(&mut core, &body(0, 550., 300., 3.));
+        spawn(&mut core, &body(1, 400., 100., 3.));
+
+        core.click(Vector2::new(550., 300.), 1., false);
+        core.circularize_selected();
+
+        let bodies = get_bodies(&core.world);
+        let snapped = bodies.iter().find(|body| body.id == 0).unwrap();
+        let circular_speed = (GRAVITATIONAL_CONSTANT * 3. * SUN_SIZE / 150.).sqrt();
+        assert!((snapped.velocity.magnitude() - circular_speed).abs() < 1e-9);
+        assert!(snapped.velocity.x.abs() < 1e-9);
+        let untouched = bodies.iter().find(|body| body.id == 1).unwrap();
+        assert_eq!(untouched.velocity, Vector2::zeros());
+    }
+
+    #[test]
+    fn reset_restores_the_initial_layout() {
+        let mut core = Core::new(Config {
+            seed: Some(21),
+            num_bodies: 30,
+            ..Config::default()
+        });
+        core.init();
+        let initial = core.snapshot();
+        let initial_count = core.body_count();
+
+        for _ in 0..50 {
+            core.pan(Vector2::new(1., 0.));
+            core.tick(0.05);
+        }
+        core.click(Vector2::new(400., 300.), 1., false);
+        core.cycle_frame_of_reference();
+        core.reset();
+
+        assert_eq!(core.body_count(), initial_count);
+        assert_eq!(core.snapshot(), initial);
+        assert_eq!(core.tick_count(), TickCount(0));
+        assert_eq!(core.frame_of_reference, FrameOfReference::Screen);
+    }
+
+    #[test]
+    fn saved_state_loads_back_as_a_scenario() {
+        let mut core = Core::new(Config {
+            seed: Some(13),
+            num_bodies: 25,
+            ..Config::default()
+        });
+        core.init();
+        for _ in 0..20 {
+            core.tick(0.05);
+        }
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-save-{}.json", std::process::id()));
+
+        core.save(&path).unwrap();
+        let mut loaded = Core::new(Config {
+            scenario: Some(path.clone()),
+            ..Config::default()
+        });
+        loaded.init();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut saved = get_bodies(&core.world);
+        let mut restored = get_bodies(&loaded.world);
+        saved.sort_by_key(|body| body.id);
+        restored.sort_by_key(|body| body.id);
+        assert_eq!(saved.len(), restored.len());
+        for (saved, restored) in saved.iter().zip(&restored) {
+            assert_eq!(saved.sun, restored.sun);
+            assert_eq!(saved.position, restored.position);
+            assert_eq!(saved.velocity, restored.velocity);
+            assert_eq!(saved.mass, restored.mass);
+            assert!((saved.radius - restored.radius).abs() < 1e-9);
+        }
+        assert!(core.save(Path::new("/nonexistent/dir/save.json")).is_err());
+    }
+
+    #[test]
+    fn additive_loads_add_up_with_fresh_ids() {
+        let write = |name: &str, json: &str| {
+            let path = std::env::temp_dir().join(format!(
+                "rusteroids-{}-{}.json",
+                name,
+                std::process::id()
+            ));
+            std::fs::write(&path, json).unwrap();
+            path
+        };
+        let system = write(
+            "system",
+            r#"{
+                "sun": {"position": [400, 300], "mass": 1000},
+                "bodies": [
+                    {"name": "inner", "position": [300, 300], "mass": 3},
+                    {"name": "outer", "position": [100, 300], "mass": 5}
+                ]
+            }"#,
+        );
+        let cluster = write(
+            "cluster",
+            r#"{
+                "sun": {"position": [0, 0], "mass": 50},
+                "bodies": [
+                    {"name": "a", "position": [700, 50], "mass": 1},
+                    {"name": "b", "position": [720, 50], "mass": 1},
+                    {"name": "c", "position": [740, 50], "mass": 1}
+                ]
+            }"#,
+        );
+
+        let mut core = Core::new(Config::default());
+        core.load_additive(&system).unwrap();
+        core.load_additive(&cluster).unwrap();
+        assert!(core
+            .load_additive(Path::new("/does/not/exist.json"))
+            .is_err());
+        std::fs::remove_file(&system).unwrap();
+        std::fs::remove_file(&cluster).unwrap();
+
+        let bodies = get_bodies(&core.world);
+        let suns = bodies.iter().filter(|body| body.sun).collect::<Vec<_>>();
+        assert_eq!(suns.len(), 1);
+        assert_eq!(suns[0].mass, 1000.);
+        let ids = bodies
+            .iter()
+            .filter(|body| !body.sun)
+            .map(|body| body.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(ids.iter().unique().count(), 5);
+        let mut names = ids
+            .iter()
+            .map(|id| core.body(*id).unwrap().name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["a", "b", "c", "inner", "outer"]);
+    }
+
+    #[test]
+    fn colors_survive_saving_and_loading() {
+        let scenario = Scenario::parse(
+            r#"{
+                "sun": {"position": [400, 300], "mass": 1000, "color": [255, 80, 0]},
+                "bodies": [
+                    {"position": [100, 100], "mass": 3, "color": [10, 200, 30]},
+                    {"position": [600, 100], "mass": 3}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut core = Core::new(Config::default());
+        core.load_scenario(&scenario);
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-colors-{}.json", std::process::id()));
+
+        core.save(&path).unwrap();
+        let saved = Scenario::load(&path).unwrap();
+        let mut loaded = Core::new(Config {
+            scenario: Some(path.clone()),
+            ..Config::default()
+        });
+        loaded.init();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved.sun.unwrap().color, Some([255, 80, 0]));
+        let colors = saved
+            .bodies
+            .iter()
+            .map(|body| body.color)
+            .collect::<Vec<_>>();
+        assert_eq!(colors, vec![Some([10, 200, 30]), None]);
+        let mut drawn = loaded
+            .draw()
+            .drawables
+            .iter()
+            .map(|drawable| (drawable.sun, drawable.color))
+            .collect::<Vec<_>>();
+        drawn.sort();
+        assert_eq!(
+            drawn,
+            vec![
+                (false, None),
+                (false, Some([10, 200, 30])),
+                (true, Some([255, 80, 0]))
+            ]
+        );
+    }
+
+    #[test]
+    fn state_hash_only_sees_changes_beyond_its_precision() {
+        let run = || {
+            let mut core = Core::new(Config {
+                seed: Some(8),
+                num_bodies: 30,
+                state_hash_precision: 1e-3,
+                ..Config::default()
+            });
+            core.init();
+            for _ in 0..20 {
+                core.tick(0.02);
+            }
+            core
+        };
+        let mut core = run();
+        let hash = core.state_hash();
+        assert_eq!(run().state_hash(), hash);
+
+        let nudge = |core: &mut Core, by: f64| {
+            for mut position in <Write<Position>>::query().iter_mut(&mut core.world).take(1) {
+                position.point.x += by;
+            }
+        };
+        nudge(&mut core, 0.01);
+        assert_ne!(core.state_hash(), hash);
+        nudge(&mut core, -0.01);
+        assert_eq!(core.state_hash(), hash);
+
+        assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn flyby_comets_go_away_after_their_closest_approach() {
+        let mut core = Core::new(Config {
+            seed: Some(2),
+            ..Config::default()
+        });
+        core.spawn_sun(Point2::new(400., 300.), SUN_SIZE, None);
+        core.spawn_comet(CometLifetime::Flyby);
+        let comet = get_bodies(&core.world)
+            .into_iter()
+            .find(|body| !body.sun)
+            .unwrap();
+        assert!(
+            ((comet.position - Point2::new(400., 300.)).magnitude() - COMET_APOAPSIS).abs() < 1e-9
+        );
+
+        let periapsis = COMET_APOAPSIS * (1. - COMET_ECCENTRICITY) / (1. + COMET_ECCENTRICITY);
+        let mut distances = vec![];
+        while let Some(comet) = core.body(comet.id) {
+            distances.push((comet.position - Point2::new(400., 300.)).magnitude());
+            assert!(distances.len() < 2000, "the comet never went away");
+            core.tick(0.05);
+        }
+
+        let closest = distances.iter().copied().fold(f64::INFINITY, f64::min);
+        assert!(
+            (closest - periapsis).abs() < periapsis * 0.1,
+            "{} vs {}",
+            closest,
+            periapsis
+        );
+        // gone right after, well before it's back out
+        assert!(distances.last().unwrap() - closest < 5.);
+        assert_eq!(core.body_count(), 1);
+    }
+
+    #[test]
+    fn comets_keep_coming_and_expire() {
+        let mut core = Core::new(Config {
+            seed: Some(2),
+            comets: Some(Comets {
+                interval: NonZeroU64::new(5).unwrap(),
+                lifetime: CometLifetime::Ticks(7),
+            }),
+            ..Config::default()
+        });
+        core.spawn_sun(Point2::new(400., 300.), SUN_SIZE, None);
+
+        let counts = (0..20)
+            .map(|_| {
+                core.tick(0.01);
+                core.body_count() - 1
+            })
+            .collect::<Vec<_>>();
+
+        // one every 5 ticks, each one around for 7
+        assert_eq!(
+            counts,
+            vec![0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 1, 1, 1, 2, 2, 1, 1, 1, 2]
+        );
+    }
+
+    #[test]
+    fn queued_spawns_trickle_in_under_the_cap() {
+        let mut core = Core::new(Config {
+            max_spawns_per_tick: NonZeroUsize::new(2),
+            ..Config::default()
+        });
+        // far enough apart that none of them merge
+        for i in 0..10 {
+            core.queue_spawn(BodySpec {
+                position: Point2::new(50. + 60. * i as f64, 100.),
+                velocity: Vector2::new(0., 0.),
+                mass: 1.,
+                density: 1.,
+                name: None,
+                color: None,
+                collision_mask: CollisionMask::default(),
+                restitution: None,
+            });
+        }
+        assert_eq!(core.body_count(), 0);
+
+        let counts = (0..6)
+            .map(|_| {
+                core.tick(0.001);
+                core.body_count()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(counts, vec![2, 4, 6, 8, 10, 10]);
+    }
+
+    #[test]
+    fn only_selected_bodies_leave_a_trail() {
+        let mut core = Core::new(Config {
+            trail_length: Some(3),
+            ..Config::default()
+        });
+        let mut moving = body(0, 100., 100., 1.);
+        moving.velocity = Vector2::new(10., 0.);
+        spawn(&mut core, &moving);
+        spawn(&mut core, &body(1, 500., 500., 1.));
+        core.tick(0.1);
+        assert!(core.trails.is_empty());
+
+        core.click(Vector2::new(101., 100.), 1., false);
+        for _ in 0..5 {
+            core.tick(0.1);
+        }
+        assert_eq!(core.trails.keys().collect::<Vec<_>>(), vec![&0]);
+        let trail = core.draw().trails.remove(0);
+        assert_eq!(trail.len(), 3);
+        assert!(trail.windows(2).all(|pair| pair[1].x > pair[0].x));
+
+        core.click(Vector2::new(300., 300.), 1., false);
+        core.tick(0.1);
+        assert!(core.trails.is_empty());
+        assert!(core.draw().trails.is_empty());
+    }
+
+    #[test]
+    fn gravity_ramps_up_over_the_first_ticks() {
+        let mut core = Core::new(Config {
+            gravity_ramp: Some(GravityRamp {
+                start: 0.,
+                ticks: NonZeroU64::new(10).unwrap(),
+            }),
+            ..Config::default()
+        });
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 200., 100., 10.));
+        assert_eq!(core.gravitational_constant(), 0.);
+
+        // nothing pulls in the very first tick
+        core.tick(0.1);
+        assert_eq!(core.body(0).unwrap().velocity, Vector2::zeros());
+        for _ in 1..10 {
+            core.tick(0.1);
+        }
+        assert_eq!(core.gravitational_constant(), GRAVITATIONAL_CONSTANT);
+        assert!(core.body(0).unwrap().velocity.x > 0.);
+    }
+
+    #[test]
+    fn merges_are_logged_with_their_tick() {
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-events-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut core = Core::new(Config {
+            event_log: Some(path.clone()),
+            ..Config::default()
+        });
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 101., 100., 5.));
+        core.tick(0.001);
+        core.tick(0.001);
+        spawn(&mut core, &body(2, 500., 500., 3.));
+        spawn(&mut core, &body(3, 501., 500., 8.));
+        core.tick(0.001);
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines = log
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines
+                .iter()
+                .map(|line| (&line["tick"], &line["kind"]))
+                .collect::<Vec<_>>(),
+            vec![
+                (&serde_json::json!(1), &serde_json::json!("impact")),
+                (&serde_json::json!(1), &serde_json::json!("merge")),
+                (&serde_json::json!(3), &serde_json::json!("impact")),
+                (&serde_json::json!(3), &serde_json::json!("merge")),
+            ]
+        );
+        assert_eq!(
+            (&lines[0]["left"], &lines[0]["right"]),
+            (&serde_json::json!(0), &serde_json::json!(1))
+        );
+        assert_eq!(
+            lines[1],
+            serde_json::json!({"tick": 1, "kind": "merge", "survivor": 0, "swallowed": 1})
+        );
+        assert_eq!(
+            lines[3],
+            serde_json::json!({"tick": 3, "kind": "merge", "survivor": 3, "swallowed": 2})
+        );
+    }
+
+    #[test]
+    fn circular_orbit_pauses_at_equilibrium() {
+        let path = std::env::temp_dir().join(format!(
+            "rusteroids-equilibrium-{}.jsonl",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        let mut core = Core::new(Config {
+            equilibrium: Some(Equilibrium {
+                ticks: NonZeroU64::new(100).unwrap(),
+                threshold: 0.01,
+            }),
+            event_log: Some(path.clone()),
+            ..Config::default()
+        });
+        let sun_position = Point2::new(400., 300.);
+        let mut sun = body(-1, sun_position.x, sun_position.y, SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        let mut planet = body(0, 600., 300., 1.);
+        planet.velocity =
+            circular_orbit_velocity(&planet.position, planet.mass, &sun_position, SUN_SIZE);
+        spawn(&mut core, &planet);
+
+        for _ in 0..99 {
+            core.tick(0.01);
+        }
+        assert!(!core.paused);
+        core.tick(0.01);
+        assert!(core.paused);
+        assert_eq!(core.tick_count(), TickCount(100));
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(log.trim()).unwrap(),
+            serde_json::json!({"tick": 100, "kind": "equilibrium_reached"})
+        );
+
+        // a new window starts after unpausing
+        core.pause();
+        core.tick(0.01);
+        assert!(!core.paused);
+    }
+
+    #[test]
+    fn age_runs_through_the_palette() {
+        let colors = AgeColors {
+            old_at: NonZeroU64::new(100).unwrap(),
+            palette: vec![Rgb([255, 255, 255]), Rgb([255, 0, 0]), Rgb([0, 0, 0])],
+        };
+        assert_eq!(age_color(&colors, 40, 40), [255, 255, 255]);
+        assert_eq!(age_color(&colors, 40, 65), [255, 128, 128]);
+        assert_eq!(age_color(&colors, 40, 90), [255, 0, 0]);
+        assert_eq!(age_color(&colors, 40, 115), [128, 0, 0]);
+        assert_eq!(age_color(&colors, 40, 140), [0, 0, 0]);
+        assert_eq!(age_color(&colors, 40, 1000), [0, 0, 0]);
+    }
+
+    #[test]
+    fn bodies_are_colored_by_when_they_were_spawned() {
+        let mut core = Core::new(Config {
+            age_colors: Some(AgeColors {
+                old_at: NonZeroU64::new(10).unwrap(),
+                palette: vec![Rgb([255, 255, 255]), Rgb([0, 0, 0])],
+            }),
+            ..Config::default()
+        });
+        let spec = |x| BodySpec {
+            position: Point2::new(x, 100.),
+            velocity: Vector2::zeros(),
+            mass: 1.,
+            density: 1.,
+            name: None,
+            color: Some([0, 255, 0]),
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        };
+        core.spawn_body(spec(100.));
+        for _ in 0..5 {
+            core.tick(0.001);
+        }
+        core.spawn_body(spec(500.));
+
+        let colors = core
+            .draw()
+            .drawables
+            .iter()
+            .sorted_by(|a, b| a.position.x.total_cmp(&b.position.x))
+            .map(|drawable| drawable.color)
+            .collect::<Vec<_>>();
+        assert_eq!(colors, vec![Some([128, 128, 128]), Some([255, 255, 255])]);
+    }
+
+    #[test]
+    fn two_body_setup_flies_the_requested_ellipse() {
+        let mut core = Core::new(Config {
+            two_body: Some(TwoBody {
+                semi_major_axis: 150.,
+                eccentricity: 0.5,
+            }),
+            ..Config::default()
+        });
+        core.init();
+        assert_eq!(core.body_count(), 2);
+
+        let mu = GRAVITATIONAL_CONSTANT * SATELLITE_MASS * SUN_SIZE;
+        let period = 2. * PI * (150_f64.powi(3) / mu).sqrt();
+        let dt = 0.01;
+        let (mut closest, mut furthest) = (f64::INFINITY, 0_f64);
+        for _ in 0..(period / dt) as usize {
+            core.tick(dt);
+            let distance = (core.body(0).unwrap().position - screen_center()).magnitude();
+            closest = closest.min(distance);
+            furthest = furthest.max(distance);
+        }
+
+        let semi_major_axis = (closest + furthest) / 2.;
+        let eccentricity = (furthest - closest) / (furthest + closest);
+        assert!((semi_major_axis - 150.).abs() < 1.5, "{}", semi_major_axis);
+        assert!((eccentricity - 0.5).abs() < 0.01, "{}", eccentricity);
+    }
+
+    #[test]
+    fn largest_body_follows_the_merges() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 101., 100., 5.));
+        spawn(&mut core, &body(2, 600., 500., 12.));
+        spawn(&mut core, &body(3, 601., 500., 4.));
+        spawn(&mut core, &body(4, 100., 500., 14.));
+        assert_eq!(core.largest_body(false).unwrap().id, 4);
+
+        core.tick(0.001);
+
+        assert_eq!(core.body_count(), 4);
+        let largest = core.largest_body(false).unwrap();
+        assert_eq!((largest.id, largest.mass), (2, 16.));
+        assert_eq!(core.largest_body(true).unwrap().id, -1);
+    }
+
+    #[test]
+    fn radius_model_decides_how_merged_bodies_grow() {
+        for &(model, growth) in &[
+            (RadiusModel::Sphere, 2_f64.cbrt()),
+            (RadiusModel::Disk, 2_f64.sqrt()),
+        ] {
+            let mut core = Core::new(Config {
+                radius_model: model,
+                ..Config::default()
+            });
+            let radius = Dimensions::from_mass(10., model).radius;
+            for &(id, x) in &[(0, 100.), (1, 101.)] {
+                let mut equal = body(id, x, 100., 10.);
+                equal.radius = radius;
+                spawn(&mut core, &equal);
+            }
+            core.tick(0.001);
+
+            assert_eq!(core.body_count(), 1);
+            let merged = core.body(0).unwrap();
+            assert!((merged.radius - radius * growth).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn trajectory_archive_replays_every_tick() {
+        let path = std::env::temp_dir().join(format!(
+            "rusteroids-trajectory-{}.bin.gz",
+            std::process::id()
+        ));
+        let mut core = Core::new(Config {
+            trajectory_archive: Some(path.clone()),
+            ..Config::default()
+        });
+        core.init();
+        let mut recorded = vec![];
+        for _ in 0..20 {
+            core.tick(0.01);
+            recorded.push(ArchivedTick {
+                tick: core.tick_count().0,
+                bodies: core.iter_bodies().sorted_by_key(|body| body.id).collect(),
+            });
+        }
+        drop(core);
+
+        let read = read_archive(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), recorded);
+    }
+
+    #[test]
+    fn trajectory_archive_survives_a_reset() {
+        let path = std::env::temp_dir().join(format!(
+            "rusteroids-trajectory-reset-{}.bin.gz",
+            std::process::id()
+        ));
+        let mut core = Core::new(Config {
+            trajectory_archive: Some(path.clone()),
+            ..Config::default()
+        });
+        core.init();
+        let mut recorded: Vec<Vec<BodyView>> = vec![];
+        for step in 0..20 {
+            if step == 10 {
+                core.reset();
+            }
+            core.tick(0.01);
+            recorded.push(core.iter_bodies().sorted_by_key(|body| body.id).collect());
+        }
+        drop(core);
+
+        let read = read_archive(&path);
+        std::fs::remove_file(&path).unwrap();
+        let read = read.unwrap();
+        assert_eq!(
+            read.iter().map(|tick| tick.tick).collect::<Vec<_>>(),
+            (1..=20).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            read.into_iter().map(|tick| tick.bodies).collect::<Vec<_>>(),
+            recorded
+        );
     }
-}
 
-pub(crate) struct Drawable {
-    pub(crate) position: Point2<f64>,
-    pub(crate) sun: bool,
-    pub(crate) radius: f64,
-    pub(crate) select_marker: bool,
-}
+    #[test]
+    fn merged_bodies_grow_with_their_mass() {
+        let mut core = Core::new(Config::default());
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 101., 100., 5.));
+        let mut dense = body(2, 500., 500., 8.);
+        dense.radius = Dimensions::from_mass_and_density(8., 27., RadiusModel::Sphere).radius;
+        spawn(&mut core, &dense);
+        spawn(&mut core, &body(3, 501., 500., 1.));
 
-fn calculate_gravitational_force(
-    position: &Point2<f64>,
-    mass: &f64,
-    other_position: &Point2<f64>,
-    other_mass: &f64,
-) -> Vector2<f64> {
-    let difference: Vector2<f64> = other_position - position;
-    let distance = difference.magnitude();
-    let gravity_direction: Vector2<f64> = difference.normalize();
-    let gravity: f64 = GRAVITATIONAL_CONSTANT * (mass * other_mass) / (distance * distance);
+        core.tick(0.001);
 
-    gravity_direction * gravity
-}
+        let merged = core.body(0).unwrap();
+        assert_eq!(merged.mass, 15.);
+        assert!(
+            (merged.radius - Dimensions::from_mass(15., RadiusModel::Sphere).radius).abs() < 1e-12
+        );
+        let dense = core.body(2).unwrap();
+        assert_eq!(dense.mass, 9.);
+        assert!(
+            (dense.radius - Dimensions::from_mass_and_density(9., 27., RadiusModel::Sphere).radius)
+                .abs()
+                < 1e-12
+        );
+    }
 
-fn are_colliding(
-    position: Point2<f64>,
-    radius: f64,
-    other_position: Point2<f64>,
-    other_radius: f64,
-) -> bool {
-    let shape = Ball::new(radius);
-    let position = Isometry2::new(position.coords, nalgebra::zero());
-    let other_shape = Ball::new(other_radius);
-    let other_position = Isometry2::new(other_position.coords, nalgebra::zero());
+    #[test]
+    fn collision_breakpoint_pauses_before_the_impact() {
+        let mut core = Core::new(Config {
+            break_on_collision: true,
+            ..Config::default()
+        });
+        let mut left = body(0, 100., 100., 10.);
+        left.velocity = Vector2::new(20., 0.);
+        left.selected = true;
+        spawn(&mut core, &left);
+        spawn(&mut core, &body(1, 110., 100., 10.));
 
-    let proximity = query::proximity(&position, &shape, &other_position, &other_shape, 0.);
-    if let Proximity::Intersecting = proximity {
-        true
-    } else {
-        false
+        let mut ticks = 0;
+        while !core.paused {
+            core.tick(0.01);
+            ticks += 1;
+            assert!(ticks < 100, "never collided");
+        }
+
+        // the triggering tick didn't happen
+        assert_eq!(core.tick_count(), TickCount(ticks - 1));
+        assert_eq!(core.body_count(), 2);
+        assert_eq!(core.collision_pairs(), &[(0, 1)]);
+        let bodies = get_bodies(&core.world);
+        let left = bodies.iter().find(|body| body.id == 0).unwrap();
+        let right = bodies.iter().find(|body| body.id == 1).unwrap();
+        assert!(!are_colliding(
+            left.position,
+            left.radius,
+            right.position,
+            right.radius,
+            0.
+        ));
+
+        // only breaks once
+        core.pause();
+        core.tick(0.01);
+        assert!(!core.paused);
+        assert_eq!(core.body_count(), 1);
     }
-}
 
-fn get_bodies(world: &World) -> Vec<Body> {
-    <(
-        Read<Position>,
-        Read<Velocity>,
-        Read<Dimensions>,
-        Read<MetaInfo>,
-        Read<Id>,
-        Read<Data>,
-    )>::query()
-    .iter(world)
-    .map(|(pos, velocity, dimensions, meta_info, id, data)| Body {
-        position: pos.point,
-        velocity: velocity.vector,
-        radius: dimensions.radius,
-        mass: dimensions.mass,
-        selected: meta_info.selected,
-        id: id.id,
-        sun: data.sun,
-        delete: false,
-    })
-    .collect::<Vec<_>>()
-}
+    #[test]
+    fn illumination_falls_off_with_distance() {
+        assert_eq!(illumination(10_000., 50.), 1.);
+        assert_eq!(illumination(10_000., 100.), 1.);
+        assert_eq!(illumination(10_000., 200.), 0.25);
+        assert!(illumination(10_000., 2000.) < 0.01);
+    }
+
+    #[test]
+    fn separation_floor_keeps_a_tight_binary_together() {
+        // closest and furthest separation over a few orbits of a binary that swings in from 4 to 1
+        let extremes = |min_separation: f64| {
+            let mut core = Core::new(Config {
+                min_separation,
+                ..Config::default()
+            });
+            // small enough to never touch, even at the closest pass
+            let mut left = body(0, 98., 100., 10.);
+            let mut right = body(1, 102., 100., 10.);
+            left.radius = 0.05;
+            right.radius = 0.05;
+            // relative speed 10 at 4 apart, with both pulled by G·10·10/d² that's the apoapsis of an orbit down to 1
+            left.velocity = Vector2::new(0., -5.);
+            right.velocity = Vector2::new(0., 5.);
+            spawn(&mut core, &left);
+            spawn(&mut core, &right);
+
+            let (mut closest, mut furthest) = (f64::INFINITY, 0_f64);
+            for _ in 0..2000 {
+                core.tick(0.001);
+                let bodies = get_bodies(&core.world);
+                assert_eq!(bodies.len(), 2);
+                let separation = (bodies[0].position - bodies[1].position).magnitude();
+                closest = closest.min(separation);
+                furthest = furthest.max(separation);
+            }
+            (closest, furthest)
+        };
+
+        let (closest, furthest) = extremes(0.);
+        assert!(closest < 1.1, "closest {}", closest);
+        assert!(furthest < 4.1, "furthest {}", furthest);
+        // inside the floor the pull stops growing, so the orbit can't swing in as deep
+        let (closest, furthest) = extremes(3.);
+        assert!(closest > 1.5 && closest < 3., "closest {}", closest);
+        assert!(furthest < 4.1, "furthest {}", furthest);
+    }
+
+    #[test]
+    fn spawned_bodies_can_be_read_back_by_id() {
+        let mut core = Core::new(Config::default());
+        let heavy = core.spawn_body(BodySpec {
+            position: Point2::new(100., 100.),
+            velocity: Vector2::new(0., 0.),
+            mass: 40.,
+            density: 1.,
+            name: Some("heavy".to_string()),
+            color: None,
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        });
+        let light = core.spawn_body(BodySpec {
+            position: Point2::new(200., 100.),
+            velocity: Vector2::new(0., 10.),
+            mass: 1.,
+            density: 8.,
+            name: Some("light".to_string()),
+            color: None,
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        });
+        assert_ne!(heavy, light);
 
-fn predict_orbit(time_step: f64, world: &World) -> Vec<Point2<f64>> {
-    let mut bodies = get_bodies(world);
+        core.tick(0.01);
 
-    let mut predicted_positions = vec![];
-    for i in 0..10000 {
-        bodies = do_one_physics_step(time_step, bodies);
-        bodies = bodies
+        let heavy = core.body(heavy).unwrap();
+        let light = core.body(light).unwrap();
+        assert_eq!(heavy.name, "heavy");
+        assert_eq!(light.name, "light");
+        assert!(heavy.position.x > 100.);
+        assert!(light.position.x < 200.);
+        assert!(light.position.y > 100.);
+        // eight times as dense, half the radius
+        assert!(
+            (light.radius - Dimensions::from_mass(1., RadiusModel::Sphere).radius / 2.).abs()
+                < 1e-12
+        );
+        assert_eq!(core.body(42), None);
+    }
+
+    #[test]
+    fn iterating_bodies_visits_each_of_them_once() {
+        let mut core = Core::new(Config::default());
+        core.init();
+        core.tick(0.01);
+
+        let views = core
+            .iter_bodies()
+            .sorted_by_key(|view| view.id)
+            .collect::<Vec<_>>();
+        let expected = get_bodies(&core.world)
             .into_iter()
-            .filter(|body| !body.delete)
+            .sorted_by_key(|body| body.id)
+            .map(|body| BodyView {
+                id: body.id,
+                position: body.position,
+                velocity: body.velocity,
+                mass: body.mass,
+                radius: body.radius,
+                sun: body.sun,
+            })
             .collect::<Vec<_>>();
-        if i % 100 == 0 {
-            let maybe_selected = bodies.iter().find(|body| body.selected);
-            if let Some(body) = maybe_selected {
-                predicted_positions.push(body.position);
+        assert!(views.len() > 1);
+        assert_eq!(views, expected);
+        assert_eq!(views.iter().filter(|view| view.sun).count(), 1);
+    }
+
+    #[test]
+    fn swallowing_several_bodies_does_not_depend_on_their_order() {
+        let mut big = body(0, 100., 100., 1000.);
+        big.velocity = Vector2::new(3., 0.);
+        let mut small = [
+            body(1, 103., 100., 1.),
+            body(2, 100., 104., 2.),
+            body(3, 96., 99., 3.),
+        ];
+        small[0].velocity = Vector2::new(-20., 5.);
+        small[1].velocity = Vector2::new(0., -30.);
+        small[2].velocity = Vector2::new(10., 10.);
+
+        let survivor = |order: &[usize]| {
+            let mut core = Core::new(Config::default());
+            spawn(&mut core, &big);
+            for i in order {
+                spawn(&mut core, &small[*i]);
             }
+            core.tick(0.001);
+            let bodies = get_bodies(&core.world);
+            assert_eq!(bodies.len(), 1);
+            bodies[0].clone()
+        };
+
+        let expected = survivor(&[0, 1, 2]);
+        assert_eq!(expected.mass, 1006.);
+        for order in &[[0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]] {
+            let other = survivor(order);
+            assert_eq!(other.velocity, expected.velocity);
+            assert_eq!(other.position, expected.position);
         }
     }
-    predicted_positions
-}
 
-// intermediare struct to pass a body around
-#[derive(Clone, Debug)]
-struct Body {
-    position: Point2<f64>,
-    velocity: Vector2<f64>,
-    radius: f64,
-    mass: f64,
-    selected: bool,
-    id: i32,
-    sun: bool,
-    delete: bool,
-}
+    #[test]
+    fn fit_to_bounds_keeps_every_body_inside_the_margin() {
+        let margin = 40.;
+        let points = [
+            Point2::new(-500., 20.),
+            Point2::new(1500., 300.),
+            Point2::new(200., -250.),
+            Point2::new(700., 900.),
+        ];
+        let view = fit_to_bounds(&points, margin).unwrap();
+        assert_eq!(view.center, Point2::new(500., 325.));
 
-fn do_one_physics_step(time_step: f64, mut bodies: Vec<Body>) -> Vec<Body> {
-    // calculate new velocities
-    let clones = bodies.clone();
-    bodies = bodies
-        .into_iter()
-        .map(|mut body| {
-            for clone in &clones {
-                if body.id == clone.id || body.sun {
-                    continue;
-                }
-                let gravitational_force = calculate_gravitational_force(
-                    &body.position,
-                    &body.mass,
-                    &clone.position,
-                    &clone.mass,
+        let on_screen = points
+            .iter()
+            .map(|point| (point - view.center) * view.zoom + screen_center().coords)
+            .collect::<Vec<_>>();
+        for point in &on_screen {
+            assert!(point.x >= margin - 1e-9 && point.x <= WIDTH as f64 - margin + 1e-9);
+            assert!(point.y >= margin - 1e-9 && point.y <= HEIGHT as f64 - margin + 1e-9);
+        }
+        // as tight as possible, the widest pair touches the margin
+        let left_edge = on_screen
+            .iter()
+            .map(|point| point.x)
+            .fold(f64::MAX, f64::min);
+        assert!((left_edge - margin).abs() < 1e-9);
+
+        assert_eq!(fit_to_bounds(&[], margin), None);
+        assert_eq!(
+            fit_to_bounds(&[Point2::new(1., 2.)], margin).unwrap().zoom,
+            MAX_OVERVIEW_ZOOM
+        );
+    }
+
+    #[test]
+    fn interpolation_draws_halfway_between_ticks() {
+        let mut core = Core::new(Config {
+            interpolate: true,
+            ..Config::default()
+        });
+        let mut moving = body(0, 100., 100., 1.);
+        moving.velocity = Vector2::new(10., 0.);
+        spawn(&mut core, &moving);
+
+        core.tick(1.);
+        let current = get_bodies(&core.world)[0].position;
+        assert_eq!(current, Point2::new(110., 100.));
+
+        let drawn = core.draw_interpolated(0.5).drawables[0].position;
+        assert_eq!(drawn, Point2::new(105., 100.));
+        assert_eq!(core.draw_interpolated(1.).drawables[0].position, current);
+
+        // without interpolation bodies are always drawn where they are
+        core.config.interpolate = false;
+        assert_eq!(core.draw_interpolated(0.5).drawables[0].position, current);
+    }
+
+    #[test]
+    fn merge_cap_defers_the_rest_to_later_ticks() {
+        let mut core = Core::new(Config {
+            max_merges_per_tick: Some(1),
+            ..Config::default()
+        });
+        // three pairs, far enough apart not to touch each other
+        for (i, x) in [100., 200., 300.].iter().enumerate() {
+            let i = i as i32;
+            spawn(&mut core, &body(2 * i, *x, 100., 20.));
+            spawn(&mut core, &body(2 * i + 1, x + 1., 100., 5.));
+        }
+
+        let mut counts = vec![];
+        for _ in 0..3 {
+            core.tick(0.0001);
+            counts.push(get_bodies(&core.world).len());
+        }
+        assert_eq!(counts, vec![5, 4, 3]);
+    }
+
+    #[test]
+    fn id_colors_are_fixed_and_spread_around_the_wheel() {
+        assert_eq!(hue_color(0.), [255, 102, 102]);
+        assert_eq!(hue_color(1. / 3.), [102, 255, 102]);
+        assert_eq!(hue_color(2. / 3.), [102, 102, 255]);
+        // pinned, so a change that would recolor every run shows up
+        assert_eq!(
+            (0..3).map(id_color).collect::<Vec<_>>(),
+            vec![[247, 255, 102], [102, 255, 194], [158, 102, 255]]
+        );
+
+        // every twelfth of the wheel gets its share, give or take
+        let mut sectors = [0; 12];
+        for id in 0..1200 {
+            sectors[(id_hue(id) * 12.) as usize] += 1;
+        }
+        assert!(
+            sectors.iter().all(|&count| count > 50 && count < 150),
+            "{:?}",
+            sectors
+        );
+        // neighbouring ids don't get similar colors, a quarter turn apart on average when random
+        let apart = (0..1200)
+            .map(|id| {
+                let turn = (id_hue(id + 1) - id_hue(id)).abs();
+                turn.min(1. - turn)
+            })
+            .sum::<f64>()
+            / 1200.;
+        assert!(apart > 0.2, "{}", apart);
+    }
+
+    #[test]
+    fn id_colors_survive_saving() {
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-id-colors-{}.json", std::process::id()));
+        let config = Config {
+            id_colors: true,
+            ..Config::default()
+        };
+        let mut core = Core::new(config.clone());
+        spawn(&mut core, &body(3, 100., 100., 1.));
+        spawn(&mut core, &body(8, 200., 100., 1.));
+        let colors = |core: &Core| {
+            core.draw()
+                .drawables
+                .iter()
+                .sorted_by(|a, b| a.position.x.total_cmp(&b.position.x))
+                .map(|drawable| drawable.color)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(colors(&core), vec![Some(id_color(3)), Some(id_color(8))]);
+
+        core.save(&path).unwrap();
+        let mut loaded = Core::new(Config {
+            scenario: Some(path.clone()),
+            ..config
+        });
+        loaded.init();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(colors(&loaded), colors(&core));
+    }
+
+    #[test]
+    fn scrubbing_draws_the_predicted_snapshot() {
+        let setup = |config: Config| {
+            let mut core = Core::new(config);
+            let mut sun = body(-1, 400., 300., SUN_SIZE);
+            sun.sun = true;
+            spawn(&mut core, &sun);
+            let mut planet = body(0, 400., 150., 5.);
+            planet.velocity =
+                circular_orbit_velocity(&planet.position, 5., &sun.position, SUN_SIZE);
+            spawn(&mut core, &planet);
+            core
+        };
+        let planet_drawn_at = |core: &Core| {
+            core.draw()
+                .drawables
+                .iter()
+                .find(|drawable| !drawable.sun)
+                .unwrap()
+                .position
+        };
+        let mut core = setup(Config {
+            inspect: true,
+            start_paused: true,
+            prediction_steps: 1000,
+            prediction_sample_interval: NonZeroUsize::new(100).unwrap(),
+            ..Config::default()
+        });
+        let now = planet_drawn_at(&core);
+        core.tick(0.01);
+        assert_eq!(
+            core.draw().scrubber,
+            Some(Scrubber {
+                index: None,
+                predicted: 10,
+                total: 10,
+                seconds_ahead: None,
+            })
+        );
+
+        core.scrub_to(Some(3));
+        let scrubber = core.draw().scrubber.unwrap();
+        assert_eq!(scrubber.index, Some(3));
+        assert!((scrubber.seconds_ahead.unwrap() - 3.01).abs() < 1e-12);
+        // the same as actually running that far
+        let mut ran = setup(Config::default());
+        for _ in 0..301 {
+            ran.tick(0.01);
+        }
+        let scrubbed = planet_drawn_at(&core);
+        assert!((scrubbed - planet_drawn_at(&ran)).magnitude() < 1e-9);
+        assert!((scrubbed - now).magnitude() > 10.);
+        assert_eq!(core.tick_count(), TickCount(0));
+
+        core.scrub(100);
+        assert_eq!(core.draw().scrubber.unwrap().index, Some(9));
+        core.scrub(-10);
+        assert_eq!(core.draw().scrubber.unwrap().index, None);
+        assert_eq!(planet_drawn_at(&core), now);
+
+        core.scrub_to(Some(2));
+        core.pause();
+        assert_eq!(core.draw().scrubber, None);
+        assert_eq!(planet_drawn_at(&core), now);
+    }
+
+    #[test]
+    fn merging_stops_at_the_body_floor() {
+        let mut core = Core::new(Config {
+            min_bodies: Some(10),
+            ..Config::default()
+        });
+        // a clump so tight everything touches everything, it would merge into a single body
+        for i in 0..25 {
+            spawn(
+                &mut core,
+                &body(
+                    i,
+                    100. + (i % 5) as f64 * 0.1,
+                    100. + (i / 5) as f64 * 0.1,
+                    10.,
+                ),
+            );
+        }
+
+        for _ in 0..20 {
+            core.tick(0.0001);
+        }
+        assert_eq!(core.body_count(), 10);
+        assert_eq!(core.iter_bodies().map(|body| body.mass).sum::<f64>(), 250.);
+    }
+
+    #[test]
+    fn selected_body_on_a_circular_orbit_reports_its_elements() {
+        let mut core = Core::new(Config::default());
+        let mut sun = body(-1, 400., 300., SUN_SIZE);
+        sun.sun = true;
+        let mut orbiting = body(0, 400., 150., 5.);
+        orbiting.velocity =
+            circular_orbit_velocity(&orbiting.position, 5., &sun.position, SUN_SIZE);
+        spawn(&mut core, &sun);
+        spawn(&mut core, &orbiting);
+
+        assert_eq!(core.draw().orbit, None);
+        core.click(Vector2::new(400., 150.), 1., false);
+        let orbit = core.draw().orbit.unwrap();
+        assert!(orbit.eccentricity < 1e-6);
+        assert!((orbit.semi_major_axis - 150.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scenario_spawns_exactly_the_listed_bodies() {
+        let scenario = Scenario::parse(
+            r#"{
+                "sun": {"position": [400, 300], "mass": 1000},
+                "bodies": [
+                    {"name": "rock", "position": [100, 100], "velocity": [1, 2], "mass": 20},
+                    {"name": "ice", "position": [600, 100], "mass": 8, "density": 8, "color": [120, 200, 255]}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let mut core = Core::new(Config::default());
+        core.load_scenario(&scenario);
+
+        let bodies = get_bodies(&core.world);
+        assert_eq!(bodies.len(), 3);
+        let sun = bodies.iter().find(|body| body.sun).unwrap();
+        assert_eq!(sun.position, Point2::new(400., 300.));
+        assert_eq!(sun.mass, 1000.);
+
+        let rock = core.body(0).unwrap();
+        assert_eq!(rock.name, "rock");
+        assert_eq!(rock.position, Point2::new(100., 100.));
+        assert_eq!(rock.velocity, Vector2::new(1., 2.));
+        assert_eq!(rock.mass, 20.);
+        let ice = core.body(1).unwrap();
+        assert_eq!(ice.name, "ice");
+        assert_eq!(
+            ice.radius,
+            Dimensions::from_mass_and_density(8., 8., RadiusModel::Sphere).radius
+        );
+        assert!(core
+            .draw()
+            .drawables
+            .iter()
+            .any(|drawable| drawable.color == Some([120, 200, 255])));
+    }
+
+    #[test]
+    fn deleted_ids_are_never_handed_out_again() {
+        let mut core = Core::new(Config::default());
+        let spec = |x: f64| BodySpec {
+            position: Point2::new(x, 100.),
+            velocity: Vector2::new(0., 0.),
+            mass: 1.,
+            density: 1.,
+            name: None,
+            color: None,
+            collision_mask: CollisionMask::default(),
+            restitution: None,
+        };
+        let first = (0..3)
+            .map(|i| core.spawn_body(spec(100. * (i + 1) as f64)))
+            .collect::<Vec<_>>();
+
+        // the newest one, the one an id based on what's left would hand out again
+        core.click(Vector2::new(300., 100.), 1., false);
+        core.delete_selected();
+        assert_eq!(core.body(first[2]), None);
+
+        let respawned = core.spawn_body(spec(300.));
+        assert!(!first.contains(&respawned));
+        assert_eq!(
+            core.body(respawned).unwrap().position,
+            Point2::new(300., 100.)
+        );
+    }
+
+    #[test]
+    fn spline_softening_is_newtonian_beyond_its_length() {
+        let spline = Some(Softening::Spline { length: 5. });
+        for distance in &[5., 5.5, 20., 1000.] {
+            assert_eq!(
+                inverse_square(*distance, spline),
+                1. / (distance * distance)
+            );
+        }
+        // continuous where the kernel hands over to newtonian, finite and weaker inside
+        assert!((inverse_square(5. - 1e-9, spline) - 1. / 25.).abs() < 1e-6);
+        assert!(inverse_square(1., spline) < 1.);
+        assert_eq!(inverse_square(0., spline), 0.);
+
+        // plummer is weaker everywhere, but converges far out
+        let plummer = Some(Softening::Plummer { length: 5. });
+        assert!(inverse_square(20., plummer) < 1. / 400.);
+        assert!((inverse_square(1000., plummer) * 1e6 - 1.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn collision_masks_decide_who_merges() {
+        const PLANETS: u32 = 0b01;
+        const DUST: u32 = 0b10;
+        let dust = CollisionMask {
+            categories: DUST,
+            collides_with: PLANETS,
+        };
+        let planet = CollisionMask {
+            categories: PLANETS,
+            collides_with: PLANETS | DUST,
+        };
+        let spec = |x: f64, mass: f64, collision_mask: CollisionMask| BodySpec {
+            position: Point2::new(x, 100.),
+            velocity: Vector2::new(0., 0.),
+            mass,
+            density: 1.,
+            name: None,
+            color: None,
+            collision_mask,
+            restitution: None,
+        };
+
+        let mut core = Core::new(Config::default());
+        let first = core.spawn_body(spec(100., 5., dust));
+        let second = core.spawn_body(spec(101., 5., dust));
+        core.tick(0.0001);
+        assert!(core.body(first).is_some());
+        assert!(core.body(second).is_some());
+
+        let mut core = Core::new(Config::default());
+        let dust = core.spawn_body(spec(100., 5., dust));
+        let planet = core.spawn_body(spec(101., 20., planet));
+        core.tick(0.0001);
+        assert_eq!(core.body(dust), None);
+        assert_eq!(core.body(planet).unwrap().mass, 25.);
+    }
+
+    #[test]
+    fn cursor_pulls_toward_itself_with_the_configured_falloff() {
+        let attractor = CursorAttractor {
+            strength: 1000.,
+            falloff: 2.,
+        };
+        let cursor = Point2::new(100., 100.);
+        let acceleration = cursor_acceleration(&attractor, &Point2::new(100., 90.), &cursor);
+        assert_eq!(acceleration, Vector2::new(0., 10.));
+        let further = cursor_acceleration(&attractor, &Point2::new(80., 100.), &cursor);
+        assert_eq!(further, Vector2::new(2.5, 0.));
+
+        let repulsor = CursorAttractor {
+            strength: -1000.,
+            ..attractor
+        };
+        let pushed = cursor_acceleration(&repulsor, &Point2::new(100., 90.), &cursor);
+        assert_eq!(pushed, Vector2::new(0., -10.));
+        assert_eq!(
+            cursor_acceleration(&attractor, &cursor, &cursor),
+            Vector2::zeros()
+        );
+    }
+
+    #[test]
+    fn hill_sphere_is_drawn_around_the_sun() {
+        let mut core = Core::new(Config {
+            hill_sphere: Some(HillSphere {
+                primary_mass: SUN_SIZE * 1000. / 3.,
+                distance: 2000.,
+            }),
+            ..Config::default()
+        });
+        assert_eq!(core.draw().hill_sphere, None);
+        core.spawn_sun(Point2::new(400., 300.), SUN_SIZE, None);
+        let sphere = core.draw().hill_sphere.unwrap();
+        assert_eq!(sphere.position, Point2::new(400., 300.));
+        assert!((sphere.radius - 200.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn corona_tracks_the_sun_radius() {
+        for sun_mass in &[SUN_SIZE, 8. * SUN_SIZE] {
+            let mut core = Core::new(Config {
+                corona: Some(3.),
+                ..Config::default()
+            });
+            let mut sun = body(-1, 400., 300., *sun_mass);
+            sun.sun = true;
+            spawn(&mut core, &sun);
+
+            for _ in 0..200 {
+                core.tick(0.01);
+                let corona = core.draw().corona.unwrap();
+                assert_eq!(corona.position, Point2::new(400., 300.));
+                let multiple = corona.radius / sun.radius;
+                assert!(
+                    (multiple - 3.).abs() <= 3. * CORONA_PULSE + 1e-9,
+                    "corona at {} times the sun radius",
+                    multiple
                 );
-                body.velocity += gravitational_force * time_step;
             }
-            body
-        })
-        .collect::<Vec<_>>();
-    // move bodies
-    bodies = bodies
-        .into_iter()
-        .map(|mut body| {
-            body.position += body.velocity * time_step;
-            body
-        })
-        .collect::<Vec<_>>();
+        }
 
-    // collision detection
-    let clones = bodies.clone();
-    bodies = bodies
-        .into_iter()
-        .map(|mut body| {
-            for clone in &clones {
-                if body.id == clone.id || body.sun {
-                    continue;
-                }
-                if are_colliding(body.position, body.radius, clone.position, clone.radius) {
-                    // the bigger body swallows the smaller one
-                    // this will happen twice for each collision, with this and other swapped, lets utilize this
-                    if body.mass > clone.mass {
-                        // when this is the bigger one, enlarge it
-                        let mass_ratio = clone.mass / body.mass;
-                        body.velocity += clone.velocity * mass_ratio;
-                        body.mass += clone.mass;
-                    } else {
-                        // when it's the smaller one, schedule it for deletion
-                        body.delete = true;
-                    }
-                }
+        let mut core = Core::new(Config::default());
+        core.init();
+        assert_eq!(core.draw().corona, None);
+    }
+
+    #[test]
+    fn update_order_shuffle_is_reproducible() {
+        let run = |shuffle_update_order: bool| {
+            let mut core = Core::new(Config {
+                seed: Some(11),
+                num_bodies: 30,
+                shuffle_update_order,
+                ..Config::default()
+            });
+            core.init();
+            for _ in 0..200 {
+                core.tick(0.005);
             }
-            body
-        })
-        .collect::<Vec<_>>();
+            core.snapshot()
+        };
 
-    bodies
-}
+        assert_eq!(run(false), run(false));
+        assert_eq!(run(true), run(true));
+        // or there'd be nothing to expose
+        assert_ne!(run(false), run(true));
+    }
 
-#[cfg(test)]
-mod tests {
-    use nalgebra::{Isometry2, Point2, Vector2};
-    use ncollide2d::query::PointQuery;
+    #[test]
+    fn shockwave_size_follows_impact_energy() {
+        let impact = |speed: f64| {
+            let mut core = Core::new(Config {
+                shockwaves: true,
+                ..Config::default()
+            });
+            spawn(&mut core, &body(0, 100., 100., 1000.));
+            let mut small = body(1, 106., 100., 8.);
+            small.velocity = Vector2::new(-speed, 0.);
+            spawn(&mut core, &small);
+            core.tick(0.0001);
+            let shockwaves = core.draw().shockwaves;
+            assert_eq!(shockwaves.len(), 1);
+            assert_eq!(shockwaves[0].opacity, 1.);
+            // gravity nudges the velocities a little during the tick
+            let survivor = get_bodies(&core.world)[0].clone();
+            let expected = shockwave_radius(impact_energy(8., small.velocity, survivor.velocity));
+            assert!((shockwaves[0].radius / expected - 1.).abs() < 0.01);
+            shockwaves[0].radius
+        };
 
-    use super::*;
+        // eight times the energy, twice the size
+        let slow = impact(100.);
+        let fast = impact(100. * 8f64.sqrt());
+        assert!((fast / slow - 2.).abs() < 0.01, "{} vs {}", fast, slow);
+    }
 
     #[test]
-    fn it_works() {
-        let vector: Vector2<f64> = Vector2::new(11., 11.);
-        let vector1 = Vector2::new(10., 10.);
+    fn frame_of_reference_stays_in_the_middle() {
+        let drawn_at = |core: &Core, id: usize| core.draw().drawables[id].position;
+        let mut core = Core::new(Config {
+            frame_of_reference: FrameOfReference::Sun,
+            ..Config::default()
+        });
+        let mut sun = body(-1, 100., 100., SUN_SIZE);
+        sun.sun = true;
+        spawn(&mut core, &sun);
+        let mut drifting = body(0, 300., 100., 1000.);
+        drifting.velocity = Vector2::new(0., 50.);
+        spawn(&mut core, &drifting);
 
-        let result: Vector2<f64> = vector1 - vector;
+        for _ in 0..10 {
+            core.tick(0.01);
+            assert_eq!(drawn_at(&core, 0), screen_center());
+        }
+        assert!((drawn_at(&core, 1) - drawn_at(&core, 0)).x > 190.);
 
-        let result = result.magnitude();
+        core.cycle_frame_of_reference();
+        let bodies = get_bodies(&core.world);
+        let barycenter = barycenter(&bodies, 1);
+        let drawn = core.draw().drawables;
+        let drawn_barycenter = Point2::new(
+            (drawn[0].position.x * SUN_SIZE + drawn[1].position.x * 1000.) / (SUN_SIZE + 1000.),
+            (drawn[0].position.y * SUN_SIZE + drawn[1].position.y * 1000.) / (SUN_SIZE + 1000.),
+        );
+        assert!((drawn_barycenter - screen_center()).magnitude() < 1e-9);
+        assert!((barycenter - screen_center()).magnitude() > 1.);
 
-        print!("{:?}", result)
+        // clicks land on what's drawn, not where it is
+        core.click(drawn[1].position.coords, 1., false);
+        assert!(get_bodies(&core.world)
+            .iter()
+            .any(|body| body.id == 0 && body.selected));
     }
 
     #[test]
-    fn test_click_inside() {
-        let cuboid = Ball::new(1.);
-        let click_pos = Point2::from(Vector2::new(11., 20.));
+    fn panning_moves_the_view_but_not_the_bodies() {
+        let mut core = Core::new(Config::default());
+        spawn(&mut core, &body(0, 100., 100., 10.));
+        spawn(&mut core, &body(1, 300., 200., 10.));
+        core.pause();
+        let positions = |core: &Core| {
+            get_bodies(&core.world)
+                .into_iter()
+                .map(|body| body.position)
+                .collect::<Vec<_>>()
+        };
+        let before = positions(&core);
 
-        let cuboid_pos = Isometry2::translation(10., 20.);
+        core.pan(Vector2::new(5., -3.));
+        core.pan(Vector2::new(1., 0.));
+        core.tick(0.01);
 
-        // Solid projection.
-        assert_eq!(cuboid.distance_to_point(&cuboid_pos, &click_pos, true), 0.0);
+        assert_eq!(positions(&core), before);
+        let drawn = core
+            .draw()
+            .drawables
+            .iter()
+            .map(|drawable| drawable.position)
+            .collect::<Vec<_>>();
+        assert_eq!(drawn, vec![Point2::new(106., 97.), Point2::new(306., 197.)]);
+        // and clicks still land on what's drawn
+        core.click(Vector2::new(306., 197.), 1., false);
+        assert!(get_bodies(&core.world)[1].selected);
+    }
+
+    #[test]
+    fn advance_is_reproducible() {
+        let advanced = || {
+            let mut core = Core::new(Config {
+                seed: Some(5),
+                num_bodies: 20,
+                ..Config::default()
+            });
+            core.init();
+            assert_eq!(core.advance(100), Ok(()));
+            assert_eq!(core.tick_count(), TickCount(100));
+            core.snapshot()
+        };
+        assert_eq!(advanced(), advanced());
+
+        let mut paused = Core::new(Config {
+            start_paused: true,
+            ..Config::default()
+        });
+        assert_eq!(paused.advance(10), Err(Paused));
+        assert_eq!(paused.tick_count(), TickCount(0));
     }
 }
@@ -0,0 +1,107 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// something that happened during a tick
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SimulationEvent {
+    Merge {
+        survivor: i32,
+        swallowed: i32,
+    },
+    /// swallowed by the sun
+    Accretion {
+        swallowed: i32,
+    },
+    /// two bodies touched, whatever that then did to them, `left` has the lower id
+    Impact {
+        left: i32,
+        right: i32,
+        point: [f64; 2],
+        speed: f64,
+    },
+    /// nothing has merged for a while and the kinetic energy has held steady, the sim paused itself
+    EquilibriumReached,
+}
+
+#[derive(Serialize)]
+struct LoggedEvent {
+    tick: u64,
+    #[serde(flatten)]
+    event: SimulationEvent,
+}
+
+/// Collects the events of a tick and appends them to a jsonl file in one go, one event per line
+pub(crate) struct EventLog {
+    path: PathBuf,
+    pending: Vec<LoggedEvent>,
+}
+
+impl EventLog {
+    pub(crate) fn new(path: PathBuf) -> EventLog {
+        EventLog {
+            path,
+            pending: vec![],
+        }
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, event: SimulationEvent) {
+        self.pending.push(LoggedEvent { tick, event });
+    }
+
+    /// writes out everything recorded since the last flush, which is dropped even if writing fails
+    pub(crate) fn flush(&mut self) -> Result<(), String> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for event in self.pending.drain(..) {
+            lines += &serde_json::to_string(&event).map_err(|error| error.to_string())?;
+            lines.push('\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|error| format!("couldn't write {}: {}", self.path.display(), error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn flushes_append_one_line_per_event() {
+        let path =
+            std::env::temp_dir().join(format!("rusteroids-log-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut log = EventLog::new(path.clone());
+
+        log.record(4, SimulationEvent::Accretion { swallowed: 7 });
+        log.flush().unwrap();
+        log.flush().unwrap();
+        log.record(
+            9,
+            SimulationEvent::Merge {
+                survivor: 1,
+                swallowed: 2,
+            },
+        );
+        log.flush().unwrap();
+
+        let written = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            written,
+            "{\"tick\":4,\"kind\":\"accretion\",\"swallowed\":7}\n\
+             {\"tick\":9,\"kind\":\"merge\",\"survivor\":1,\"swallowed\":2}\n"
+        );
+    }
+}
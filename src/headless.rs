@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+
+use nalgebra::Point2;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::core::{Core, Fate};
+
+/// where a headless run ended up
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct RunMetrics {
+    pub(crate) ticks: u64,
+    /// the time limit ran out before all ticks were done
+    pub(crate) timed_out: bool,
+    /// the simulation reached equilibrium and paused itself before all ticks were done
+    pub(crate) settled: bool,
+    pub(crate) bodies: usize,
+    pub(crate) largest_mass: f64,
+    /// id of the heaviest body apart from the sun, none once everything has fallen into it
+    pub(crate) largest_body: Option<i32>,
+    /// see `Core::state_hash`
+    pub(crate) state_hash: u64,
+    /// how many bodies end up on each kind of path around the sun, see `Core::classify_bodies`
+    pub(crate) bound: usize,
+    pub(crate) escaping: usize,
+    pub(crate) colliding: usize,
+}
+
+/// what gets logged every `log_interval` ticks
+#[derive(Clone, Debug, PartialEq)]
+struct Progress {
+    tick: u64,
+    bodies: usize,
+    largest_mass: f64,
+    energy: f64,
+    barycenter: Point2<f64>,
+}
+
+fn progress(core: &Core) -> Progress {
+    let diagnostics = core.diagnostics();
+    Progress {
+        tick: core.tick_count().0,
+        bodies: core.body_count(),
+        largest_mass: core.largest_mass(),
+        energy: diagnostics.energy,
+        barycenter: diagnostics.barycenter,
+    }
+}
+
+/// steps a freshly initialized simulation `ticks` times without a window,
+/// stopping early once `time_limit` has passed so a pathological config can't hang a batch,
+/// or once it has settled when `--equilibrium` is set
+pub(crate) fn run(config: Config, ticks: u64, time_limit: Option<Duration>) -> RunMetrics {
+    let started = Instant::now();
+    let log_interval = config.log_interval;
+    // nobody's around to unpause it
+    let mut core = Core::new(Config {
+        start_paused: false,
+        ..config
+    });
+    core.init();
+
+    let mut timed_out = false;
+    let mut settled = false;
+    for _ in 0..ticks {
+        if time_limit.is_some_and(|limit| started.elapsed() >= limit) {
+            timed_out = true;
+            break;
+        }
+        // only reaching equilibrium pauses it, and nobody's around to unpause it again
+        if core.advance(1).is_err() {
+            settled = true;
+            break;
+        }
+        if let Some(interval) = log_interval {
+            if core.tick_count().0.is_multiple_of(interval.get()) {
+                let progress = progress(&core);
+                info!(
+                    tick = progress.tick,
+                    bodies = progress.bodies,
+                    largest_mass = progress.largest_mass,
+                    energy = progress.energy,
+                    barycenter_x = progress.barycenter.x,
+                    barycenter_y = progress.barycenter.y,
+                    "progress"
+                );
+            }
+        }
+    }
+
+    if let Err(error) = core.finish_recording() {
+        error!("{}", error);
+    }
+
+    let fates = core.classify_bodies();
+    let count = |fate: Fate| fates.iter().filter(|(_, other)| *other == fate).count();
+    RunMetrics {
+        ticks: core.tick_count().0,
+        timed_out,
+        settled,
+        bodies: core.body_count(),
+        largest_mass: core.largest_mass(),
+        largest_body: core.largest_body(false).map(|body| body.id),
+        state_hash: core.state_hash(),
+        bound: count(Fate::Bound),
+        escaping: count(Fate::Escaping),
+        colliding: count(Fate::Colliding),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+
+    fn config(num_bodies: i32) -> Config {
+        Config {
+            seed: Some(3),
+            num_bodies,
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn runs_every_tick_without_a_limit() {
+        let metrics = run(config(5), 20, None);
+        assert_eq!(metrics.ticks, 20);
+        assert!(!metrics.timed_out);
+        // everything but the sun
+        assert_eq!(
+            metrics.bound + metrics.escaping + metrics.colliding,
+            metrics.bodies - 1
+        );
+    }
+
+    #[test]
+    fn same_config_ends_in_the_same_state() {
+        assert_eq!(
+            run(config(20), 30, None).state_hash,
+            run(config(20), 30, None).state_hash
+        );
+    }
+
+    #[test]
+    fn deterministic_sum_reproduces_shuffled_runs() {
+        // the same layout either way, only the order bodies are processed in differs
+        let state_hash = |shuffle_update_order: bool, deterministic_sum: bool| {
+            let config = Config {
+                shuffle_update_order,
+                deterministic_sum,
+                // fine enough to see the last bits of a sum, which take a while to add up
+                state_hash_precision: 1e-15,
+                ..config(20)
+            };
+            run(config, 200, None).state_hash
+        };
+
+        assert_ne!(state_hash(false, false), state_hash(true, false));
+        assert_eq!(state_hash(false, true), state_hash(true, true));
+    }
+
+    #[test]
+    fn equilibrium_stops_the_run_early() {
+        let metrics = run(
+            Config {
+                equilibrium: Some("10:1".parse().unwrap()),
+                ..config(5)
+            },
+            1000,
+            None,
+        );
+        assert!(metrics.settled);
+        assert!(!metrics.timed_out);
+        assert_eq!(metrics.ticks, 10);
+    }
+
+    #[test]
+    fn time_limit_stops_the_run_early() {
+        let requested = 1_000_000;
+        let metrics = run(config(50), requested, Some(Duration::from_millis(20)));
+        assert!(metrics.timed_out);
+        assert!(metrics.ticks < requested);
+        // partial, but still describes a real state
+        assert!(metrics.bodies > 1 && metrics.bodies <= 51);
+        assert!(metrics.largest_mass > 0.);
+        assert!(metrics.largest_body.is_some());
+    }
+
+    #[test]
+    fn progress_describes_the_current_state() {
+        let mut core = Core::new(Config::default());
+        core.load_scenario(
+            &Scenario::parse(
+                r#"{"bodies": [
+                    {"position": [0, 0], "mass": 4},
+                    {"position": [10, 0], "velocity": [0, 4], "mass": 1}
+                ]}"#,
+            )
+            .unwrap(),
+        );
+
+        let progress = progress(&core);
+
+        assert_eq!(progress.tick, 0);
+        assert_eq!(progress.bodies, 2);
+        assert_eq!(progress.largest_mass, 4.);
+        // 0.5 * 4² kinetic, -5 * 4 * 1 / 10 potential
+        assert!((progress.energy - 6.).abs() < 1e-12);
+        assert_eq!(progress.barycenter, Point2::new(2., 0.));
+    }
+}
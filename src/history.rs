@@ -0,0 +1,43 @@
+use std::collections::VecDeque;
+
+/// Ring buffer of `(tick, value)` samples backing the live graphs, the oldest sample is dropped when full
+#[derive(Clone, Debug)]
+pub(crate) struct SampleBuffer {
+    capacity: usize,
+    samples: VecDeque<(u64, f64)>,
+}
+
+impl SampleBuffer {
+    pub(crate) fn new(capacity: usize) -> SampleBuffer {
+        SampleBuffer {
+            capacity,
+            samples: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn push(&mut self, tick: u64, value: f64) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((tick, value));
+    }
+
+    /// oldest first
+    pub(crate) fn samples(&self) -> Vec<(u64, f64)> {
+        self.samples.iter().copied().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_oldest_sample_when_full() {
+        let mut buffer = SampleBuffer::new(3);
+        for tick in 0..5 {
+            buffer.push(tick, tick as f64 * 2.);
+        }
+        assert_eq!(buffer.samples(), vec![(2, 4.), (3, 6.), (4, 8.)]);
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// hands out body ids, never the same one twice so ids stay valid after deletions
+#[derive(Debug, Default)]
+pub(crate) struct IdAllocator {
+    next: AtomicI32,
+}
+
+impl IdAllocator {
+    pub(crate) fn next(&self) -> i32 {
+        self.next.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn ids_are_unique_across_threads() {
+        let allocator = Arc::new(IdAllocator::default());
+        let handles = (0..4)
+            .map(|_| {
+                let allocator = allocator.clone();
+                thread::spawn(move || (0..1000).map(|_| allocator.next()).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        let ids = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.len(), 4000);
+    }
+}
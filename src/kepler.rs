@@ -0,0 +1,181 @@
+use std::f64::consts::PI;
+
+use nalgebra::Vector2;
+
+/// shape of a bound orbit around a fixed center, lengths in pixels and the period in seconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct OrbitalElements {
+    pub(crate) semi_major_axis: f64,
+    pub(crate) eccentricity: f64,
+    /// closest approach to the center
+    pub(crate) periapsis: f64,
+    /// furthest distance from the center
+    pub(crate) apoapsis: f64,
+    pub(crate) period: f64,
+}
+
+/// elements of the orbit through `offset` with `velocity`, both relative to the center
+/// `mu` is the acceleration times distance squared the center pulls with, None on escape trajectories
+pub(crate) fn orbital_elements(
+    offset: Vector2<f64>,
+    velocity: Vector2<f64>,
+    mu: f64,
+) -> Option<OrbitalElements> {
+    let distance = offset.magnitude();
+    if distance == 0. || mu <= 0. {
+        return None;
+    }
+    let specific_energy = specific_energy(offset, velocity, mu);
+    if specific_energy >= 0. {
+        return None;
+    }
+    let semi_major_axis = -mu / (2. * specific_energy);
+    let angular_momentum = offset.x * velocity.y - offset.y * velocity.x;
+    // rounding can push a circular orbit just below zero
+    let eccentricity = (1.
+        + 2. * specific_energy * angular_momentum * angular_momentum / (mu * mu))
+        .max(0.)
+        .sqrt();
+    Some(OrbitalElements {
+        semi_major_axis,
+        eccentricity,
+        periapsis: semi_major_axis * (1. - eccentricity),
+        apoapsis: semi_major_axis * (1. + eccentricity),
+        period: 2. * PI * (semi_major_axis.powi(3) / mu).sqrt(),
+    })
+}
+
+/// whether a body at `offset` moving at `velocity` has the energy to escape the center for good,
+/// arguments as for `orbital_elements`
+pub(crate) fn is_unbound(offset: Vector2<f64>, velocity: Vector2<f64>, mu: f64) -> bool {
+    offset.magnitude() > 0. && specific_energy(offset, velocity, mu) >= 0.
+}
+
+/// closest the path through `offset` with `velocity` comes to the center, on bound and escape
+/// trajectories alike, 0 when heading straight in or out, arguments as for `orbital_elements`
+pub(crate) fn periapsis_distance(offset: Vector2<f64>, velocity: Vector2<f64>, mu: f64) -> f64 {
+    let angular_momentum = offset.x * velocity.y - offset.y * velocity.x;
+    let eccentricity = (1.
+        + 2. * specific_energy(offset, velocity, mu) * angular_momentum * angular_momentum
+            / (mu * mu))
+        .max(0.)
+        .sqrt();
+    angular_momentum * angular_momentum / mu / (1. + eccentricity)
+}
+
+/// radius of the hill sphere of a body of `mass` circling `primary_mass` at `distance`,
+/// inside it the body's own pull holds on to satellites against the primary's tide
+pub(crate) fn hill_radius(mass: f64, primary_mass: f64, distance: f64) -> f64 {
+    distance * (mass / (3. * primary_mass)).cbrt()
+}
+
+/// `(offset, velocity)` relative to the center of a body at the apoapsis of the orbit with
+/// `semi_major_axis` and `eccentricity`, the apoapsis is along x and the orbit runs counter clockwise,
+/// `mu` as for `orbital_elements`
+pub(crate) fn state_at_apoapsis(
+    semi_major_axis: f64,
+    eccentricity: f64,
+    mu: f64,
+) -> (Vector2<f64>, Vector2<f64>) {
+    let apoapsis = semi_major_axis * (1. + eccentricity);
+    // vis-viva at the far end
+    let speed = (mu / semi_major_axis * (1. - eccentricity) / (1. + eccentricity)).sqrt();
+    (Vector2::new(apoapsis, 0.), Vector2::new(0., speed))
+}
+
+/// vis-viva, kinetic plus potential energy per unit of the body's mass
+fn specific_energy(offset: Vector2<f64>, velocity: Vector2<f64>, mu: f64) -> f64 {
+    velocity.magnitude_squared() / 2. - mu / offset.magnitude()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circular_orbit_has_no_eccentricity() {
+        let mu: f64 = 5000.;
+        let radius = 200.;
+        let speed = (mu / radius).sqrt();
+        let elements =
+            orbital_elements(Vector2::new(0., radius), Vector2::new(speed, 0.), mu).unwrap();
+        assert!(elements.eccentricity < 1e-6);
+        assert!((elements.semi_major_axis - radius).abs() < 1e-9);
+        assert!((elements.periapsis - radius).abs() < 1e-9);
+        assert!((elements.apoapsis - radius).abs() < 1e-9);
+        assert!((elements.period - 2. * PI * radius / speed).abs() < 1e-9);
+    }
+
+    #[test]
+    fn apoapsis_of_a_slowed_down_orbit() {
+        let mu: f64 = 5000.;
+        let radius = 200.;
+        // slower than circular, so this is the apoapsis of an ellipse
+        let speed = (mu / radius).sqrt() * 0.8;
+        let elements =
+            orbital_elements(Vector2::new(radius, 0.), Vector2::new(0., speed), mu).unwrap();
+        assert!((elements.apoapsis - radius).abs() < 1e-9);
+        assert!(elements.periapsis < radius);
+        assert!(elements.eccentricity > 0.3 && elements.eccentricity < 0.4);
+    }
+
+    #[test]
+    fn escape_trajectory_has_no_elements() {
+        let mu: f64 = 5000.;
+        let radius = 200.;
+        let escape_speed = (2. * mu / radius).sqrt();
+        assert_eq!(
+            orbital_elements(
+                Vector2::new(radius, 0.),
+                Vector2::new(0., escape_speed * 1.01),
+                mu
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn escape_speed_separates_bound_from_unbound() {
+        let mu: f64 = 5000.;
+        let radius = 100.;
+        // sqrt(2 * 5000 / 100)
+        let escape_speed = 10.;
+        let offset = Vector2::new(radius, 0.);
+        assert!(!is_unbound(
+            offset,
+            Vector2::new(0., escape_speed * 0.99),
+            mu
+        ));
+        assert!(!is_unbound(
+            offset,
+            Vector2::new(0., (mu / radius).sqrt()),
+            mu
+        ));
+        assert!(is_unbound(offset, Vector2::new(0., escape_speed), mu));
+        assert!(is_unbound(
+            offset,
+            Vector2::new(-escape_speed * 1.01, 0.),
+            mu
+        ));
+        assert!(is_unbound(offset, Vector2::new(0., 1.), 0.));
+    }
+
+    #[test]
+    fn hill_radius_follows_the_cube_root_of_the_mass_ratio() {
+        // a thousandth of three times the primary, so a tenth of the distance
+        assert!((hill_radius(3., 1000., 100.) - 10.).abs() < 1e-12);
+        // earth around the sun, about 1.5 million km
+        let earth = hill_radius(5.972e24, 1.989e30, 1.496e8);
+        assert!((earth - 1.4966e6).abs() < 1e3);
+    }
+
+    #[test]
+    fn apoapsis_state_has_the_requested_elements() {
+        let mu = 5000.;
+        let (offset, velocity) = state_at_apoapsis(150., 0.4, mu);
+        let elements = orbital_elements(offset, velocity, mu).unwrap();
+        assert!((elements.semi_major_axis - 150.).abs() < 1e-9);
+        assert!((elements.eccentricity - 0.4).abs() < 1e-9);
+        assert!((elements.apoapsis - offset.magnitude()).abs() < 1e-9);
+    }
+}
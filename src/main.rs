@@ -1,15 +1,41 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+use nalgebra::Vector2;
 use quicksilver::blinds::event::MouseButton::Left;
 use quicksilver::geom::{Circle, Rectangle};
-use quicksilver::graphics::VectorFont;
+use quicksilver::graphics::{Element, Mesh, VectorFont, Vertex};
 use quicksilver::input::{Event, Key, ScrollDelta};
 use quicksilver::{
     geom::Vector, graphics::Color, run, Graphics, Input, Result, Settings, Timer, Window,
 };
+use tracing::{error, info, warn};
 
-use crate::core::Core;
-use crate::util::convert;
+use crate::config::Config;
+use crate::core::{Core, TimeToImpact};
+use crate::svg::write_path_svg;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::util::frame_delay;
+use crate::util::{
+    collision_outline, convert, graph_points, label_opacity, level_of_detail, lit_color,
+    render_radius, thick_line, Detail,
+};
 
+mod archive;
+mod broadphase;
+mod config;
 mod core;
+mod events;
+mod headless;
+mod history;
+mod ids;
+mod kepler;
+mod quadtree;
+mod reduce;
+mod scenario;
+mod svg;
 mod util;
 
 // use 144 fps for non wasm release, use 60 fps for wasm or debug
@@ -30,7 +56,57 @@ pub(crate) const INITIAL_SPEED: i32 = 50;
 pub(crate) const SUN_SIZE: f64 = 1000.;
 pub(crate) const GRAVITATIONAL_CONSTANT: f64 = 5.;
 
+// discs the sun corona is built from, more gives a smoother gradient
+const CORONA_RINGS: u32 = 8;
+
+// extra key bindings, listed in the top right corner
+const CONTROLS: &[&str] = &[
+    "<Shift> + click add to selection",
+    "<B> toggle graph of the two heaviest bodies' distance",
+    "<C> hold to pull bodies toward the cursor",
+    "<Delete> delete selected bodies",
+    "<F> pin or release selected bodies",
+    "<G> toggle collision grid",
+    "<K> put selected bodies on circular orbits",
+    "<Left>/<Right> scrub the prediction, paused with --inspect",
+    "<M> toggle largest mass graph",
+    "<N> start over with the same settings",
+    "<O> fit every body on screen",
+    "<R> center on screen, sun or barycenter",
+    "<T> toggle measuring, click two bodies or spots",
+    "<V> toggle collision outlines",
+    "<X> export predicted orbit as svg",
+];
+
 fn main() {
+    // the default format timestamps every line, which the web has no clock for
+    #[cfg(not(target_arch = "wasm32"))]
+    tracing_subscriber::fmt::init();
+    let config = Config::from_args(std::env::args().skip(1)).unwrap_or_else(|error| {
+        warn!("{}, falling back to the default config", error);
+        Config::default()
+    });
+    if let Some(ticks) = config.headless_ticks {
+        let time_limit = config.time_limit.map(Duration::from_secs_f64);
+        let metrics = headless::run(config, ticks, time_limit);
+        println!(
+            "ticks={} timed_out={} settled={} bodies={} largest_mass={} largest_body={} bound={} escaping={} colliding={} state_hash={:016x}",
+            metrics.ticks,
+            metrics.timed_out,
+            metrics.settled,
+            metrics.bodies,
+            metrics.largest_mass,
+            metrics
+                .largest_body
+                .map_or_else(|| "none".to_owned(), |id| id.to_string()),
+            metrics.bound,
+            metrics.escaping,
+            metrics.colliding,
+            metrics.state_hash
+        );
+        return;
+    }
+
     run(
         Settings {
             title: "Rusteroids",
@@ -38,14 +114,23 @@ fn main() {
                 x: WIDTH,
                 y: HEIGHT,
             },
+            multisampling: if config.antialiasing { Some(4) } else { None },
             ..Settings::default()
         },
-        app,
+        move |window, gfx, input| app(window, gfx, input, config),
     );
 }
 
-async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()> {
-    let mut core = Core::new();
+async fn app(window: Window, mut gfx: Graphics, mut input: Input, config: Config) -> Result<()> {
+    let min_render_radius = config.min_render_radius;
+    let point_render_radius = config.point_render_radius;
+    let orbit_line_width = config.orbit_line_width;
+    let max_fps = config.max_fps;
+    let mut shutdown = Shutdown {
+        save_to: config.save_on_exit.clone(),
+        core: Core::new(config),
+    };
+    let core = &mut shutdown.core;
     core.init();
     let mut frames: u32 = 0;
     let mut last_fps: u32 = 0;
@@ -55,17 +140,23 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
     // One to provide an consistant update time, so our example updates 30 times per second
     // the other informs us when to draw the next frame, this causes our example to draw 60 times per second
     let mut update_timer = Timer::time_per_second(UPS);
-    let mut draw_timer = Timer::time_per_second(FPS);
+    let mut draw_timer = Timer::time_per_second(max_fps.map_or(FPS, |max_fps| max_fps.min(FPS)));
     let mut fps_timer = Timer::time_per_second(1.);
 
     let ttf = VectorFont::from_slice(include_bytes!("BebasNeue-Regular.ttf"));
     let mut font = ttf.to_renderer(&gfx, 30.0)?;
+    let mut label_font = ttf.to_renderer(&gfx, 14.0)?;
 
     let mut running = true;
     let mut camera_y_axis;
     let mut camera_x_axis;
     let mut zoom_scale = 1.;
+    let mut show_collision_outlines = false;
+    let mut measuring = false;
     while running {
+        // the web has no clock or sleep, frames are only capped through the draw timer there
+        #[cfg(not(target_arch = "wasm32"))]
+        let frame_start = max_fps.map(|_| Instant::now());
         camera_y_axis = 0.;
         camera_x_axis = 0.;
         while let Some(event) = input.next_event().await {
@@ -73,11 +164,55 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
                 if !pointer_input_event.is_down() && pointer_input_event.button() == Left {
                     let mouse_position = input.mouse().location();
 
-                    core.click(convert(mouse_position));
+                    let click_position = convert(mouse_position) / zoom_scale as f64;
+                    if measuring {
+                        core.measure(click_position, zoom_scale as f64);
+                    } else {
+                        core.click(
+                            click_position,
+                            zoom_scale as f64,
+                            input.key_down(Key::LShift),
+                        );
+                    }
                 }
             } else if let Event::KeyboardInput(keyboard_event) = event {
                 if keyboard_event.is_down() && keyboard_event.key() == Key::Space {
                     core.pause();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::G {
+                    core.toggle_broadphase_overlay();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::B {
+                    core.toggle_separation_graph();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::Delete {
+                    core.delete_selected();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::F {
+                    core.toggle_static_selected();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::K {
+                    core.circularize_selected();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::M {
+                    core.toggle_mass_graph();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::N {
+                    core.reset();
+                    zoom_scale = 1.;
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::R {
+                    core.cycle_frame_of_reference();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::T {
+                    measuring = !measuring;
+                    core.clear_measurement();
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::V {
+                    show_collision_outlines = !show_collision_outlines;
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::X {
+                    let path = Path::new("orbit.svg");
+                    if let Err(error) = write_path_svg(path, &core.draw().predicted_orbit) {
+                        error!("couldn't export the predicted orbit: {}", error);
+                    }
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::O {
+                    if let Some(zoom) = core.overview() {
+                        zoom_scale = zoom as f32;
+                    }
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::Left {
+                    core.scrub(-1);
+                } else if keyboard_event.is_down() && keyboard_event.key() == Key::Right {
+                    core.scrub(1);
                 } else if keyboard_event.is_down() && keyboard_event.key() == Key::Escape {
                     running = false;
                 }
@@ -99,10 +234,16 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
         if input.key_down(Key::A) {
             camera_x_axis = 1.;
         }
+        if input.key_down(Key::C) {
+            core.set_attractor(Some(convert(input.mouse().location()) / zoom_scale as f64));
+        } else {
+            core.set_attractor(None);
+        }
 
         // We use a while loop rather than an if so that we can try to catch up in the event of having a slow down.
         while update_timer.tick() {
-            core.tick(dt, camera_x_axis, camera_y_axis);
+            core.pan(Vector2::new(camera_x_axis, camera_y_axis));
+            core.tick(dt);
         }
 
         // Unlike the update cycle drawing doesn't change our state
@@ -111,9 +252,52 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
         if draw_timer.exhaust().is_some() {
             gfx.clear(Color::BLACK);
 
-            let (drawables, predicted_orbit) = core.draw();
-            let num_bodies = drawables.len();
-            for drawable in drawables {
+            // how far into the next physics tick we are
+            let overstep_fraction = update_timer.elapsed().as_secs_f64() * UPS as f64;
+            let frame = core.draw_interpolated(overstep_fraction);
+            if let Some(corona) = frame.corona {
+                // overlapping translucent discs, brightest toward the middle
+                for ring in 0..CORONA_RINGS {
+                    let radius = corona.radius * (CORONA_RINGS - ring) as f64 / CORONA_RINGS as f64;
+                    let circle = Circle::new(
+                        Vector::new(
+                            corona.position.x as f32 * zoom_scale,
+                            corona.position.y as f32 * zoom_scale,
+                        ),
+                        radius as f32 * zoom_scale,
+                    );
+                    gfx.fill_circle(&circle, Color::ORANGE.with_alpha(0.06));
+                }
+            }
+            if let Some(hill_sphere) = frame.hill_sphere {
+                let circle = Circle::new(
+                    Vector::new(
+                        hill_sphere.position.x as f32 * zoom_scale,
+                        hill_sphere.position.y as f32 * zoom_scale,
+                    ),
+                    hill_sphere.radius as f32 * zoom_scale,
+                );
+                gfx.stroke_circle(&circle, Color::CYAN.with_alpha(0.5));
+            }
+            for cell in frame.grid_cells {
+                let rectangle = Rectangle::new(
+                    Vector::new(
+                        cell.corner.x as f32 * zoom_scale,
+                        cell.corner.y as f32 * zoom_scale,
+                    ),
+                    Vector::new(cell.size as f32, cell.size as f32) * zoom_scale,
+                );
+                let color = match cell.occupancy {
+                    1 => Color::GREEN,
+                    2 => Color::YELLOW,
+                    3 => Color::ORANGE,
+                    _ => Color::RED,
+                };
+                gfx.stroke_rect(&rectangle, color);
+            }
+
+            let num_bodies = frame.drawables.len();
+            for drawable in frame.drawables {
                 if drawable.select_marker {
                     let rectangle = Rectangle::new(
                         Vector::new(
@@ -129,22 +313,111 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
                             drawable.position.x as f32 * zoom_scale,
                             drawable.position.y as f32 * zoom_scale,
                         ),
-                        drawable.radius as f32 * zoom_scale,
-                    );
-                    gfx.fill_circle(
-                        &circle,
-                        match drawable.sun {
-                            true => Color::YELLOW,
-                            false => Color::WHITE,
+                        if drawable.debris {
+                            drawable.radius as f32 * zoom_scale
+                        } else {
+                            render_radius(drawable.radius, zoom_scale, min_render_radius)
                         },
                     );
+                    let color = match (drawable.sun, drawable.debris, drawable.color) {
+                        (_, true, _) => Color::WHITE.with_alpha(0.5),
+                        (_, false, Some([r, g, b])) => Color::from_rgba(r, g, b, 1.),
+                        (true, false, None) => Color::YELLOW,
+                        (false, false, None) => {
+                            drawable.illumination.map_or(Color::WHITE, lit_color)
+                        }
+                    };
+                    match level_of_detail(drawable.radius, zoom_scale, point_render_radius) {
+                        Detail::Point => {
+                            gfx.fill_rect(&Rectangle::new(circle.pos, Vector::ONE), color)
+                        }
+                        Detail::Circle => gfx.fill_circle(&circle, color),
+                    }
+                    if show_collision_outlines && !drawable.debris {
+                        gfx.stroke_circle(
+                            &collision_outline(drawable.position, drawable.radius, zoom_scale),
+                            Color::RED,
+                        );
+                    }
+                    let opacity = label_opacity(zoom_scale);
+                    if let (Some(label), true) = (drawable.label, opacity > 0.) {
+                        label_font.draw(
+                            &mut gfx,
+                            &label,
+                            Color::WHITE.with_alpha(opacity),
+                            circle.pos + Vector::new(circle.radius + 2., 0.),
+                        )?;
+                    }
                 }
             }
 
-            for orbit_point in predicted_orbit {
-                let circle =
-                    Circle::new(Vector::new(orbit_point.x as f32, orbit_point.y as f32), 1.);
-                gfx.fill_circle(&circle, Color::YELLOW);
+            for shockwave in frame.shockwaves {
+                let circle = Circle::new(
+                    Vector::new(
+                        shockwave.position.x as f32 * zoom_scale,
+                        shockwave.position.y as f32 * zoom_scale,
+                    ),
+                    shockwave.radius as f32 * zoom_scale,
+                );
+                gfx.stroke_circle(&circle, Color::WHITE.with_alpha(shockwave.opacity as f32));
+            }
+
+            for trail in frame.trails {
+                let points = trail
+                    .iter()
+                    .map(|point| Vector::new(point.x as f32, point.y as f32) * zoom_scale)
+                    .collect::<Vec<_>>();
+                gfx.stroke_path(&points, Color::WHITE.with_alpha(0.5));
+            }
+
+            if let Some(measurement) = frame.measurement {
+                let from = Vector::new(
+                    measurement.from.x as f32 * zoom_scale,
+                    measurement.from.y as f32 * zoom_scale,
+                );
+                let to = Vector::new(
+                    measurement.to.x as f32 * zoom_scale,
+                    measurement.to.y as f32 * zoom_scale,
+                );
+                gfx.stroke_path(&[from, to], Color::CYAN);
+                let text = match measurement.motion {
+                    Some(motion) => format!(
+                        "{:.1} apart, {:.1} relative speed, closing at {:.1}",
+                        measurement.distance,
+                        motion.velocity.magnitude(),
+                        motion.closing_speed
+                    ),
+                    None => format!("{:.1} apart", measurement.distance),
+                };
+                label_font.draw(&mut gfx, &text, Color::CYAN, (from + to) / 2.)?;
+            }
+
+            match orbit_line_width {
+                Some(width) => {
+                    let line = thick_line(&frame.predicted_orbit, width);
+                    gfx.draw_mesh(&Mesh {
+                        vertices: line
+                            .vertices
+                            .into_iter()
+                            .map(|pos| Vertex {
+                                pos,
+                                uv: None,
+                                color: Color::YELLOW,
+                            })
+                            .collect(),
+                        elements: line.triangles.into_iter().map(Element::Triangle).collect(),
+                        image: None,
+                    });
+                }
+                None => {
+                    for orbit_point in frame.predicted_orbit {
+                        let circle = Circle::new(
+                            Vector::new(orbit_point.x as f32, orbit_point.y as f32),
+                            1.,
+                        );
+                        gfx.fill_circle(&circle, Color::YELLOW);
+                    }
+                }
             }
 
             font.draw(
@@ -153,6 +426,60 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
                 Color::GREEN,
                 Vector::new(10.0, 60.0),
             )?;
+            font.draw(
+                &mut gfx,
+                format!("Tick: {}", core.tick_count().0).as_str(),
+                Color::GREEN,
+                Vector::new(10.0, 90.0),
+            )?;
+            if let Some(time_to_impact) = frame.time_to_impact {
+                let readout = match time_to_impact {
+                    TimeToImpact::Stable => "Impact: stable".to_string(),
+                    TimeToImpact::Seconds(seconds) => format!("Impact in {:.1}s", seconds),
+                };
+                font.draw(&mut gfx, &readout, Color::GREEN, Vector::new(10.0, 120.0))?;
+            }
+            if let Some(orbit) = frame.orbit {
+                font.draw(
+                    &mut gfx,
+                    format!(
+                        "Orbit: a {:.0} e {:.2} peri {:.0} apo {:.0} period {:.1}s",
+                        orbit.semi_major_axis,
+                        orbit.eccentricity,
+                        orbit.periapsis,
+                        orbit.apoapsis,
+                        orbit.period
+                    )
+                    .as_str(),
+                    Color::GREEN,
+                    Vector::new(10.0, 150.0),
+                )?;
+            }
+            if let Some(group) = frame.group {
+                let state = if group.energy < 0. {
+                    "bound"
+                } else {
+                    "unbound"
+                };
+                font.draw(
+                    &mut gfx,
+                    format!("Group energy: {:.0} ({})", group.energy, state).as_str(),
+                    Color::GREEN,
+                    Vector::new(10.0, 150.0),
+                )?;
+                font.draw(
+                    &mut gfx,
+                    format!(
+                        "Group momentum: {:.0} around ({:.0}, {:.0})",
+                        group.momentum.magnitude(),
+                        group.barycenter.x,
+                        group.barycenter.y
+                    )
+                    .as_str(),
+                    Color::GREEN,
+                    Vector::new(10.0, 180.0),
+                )?;
+            }
 
             frames += 1;
             if fps_timer.tick() {
@@ -179,8 +506,110 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
                 Vector::new(10.0, HEIGHT - 40.),
             )?;
 
+            if !frame.largest_mass_history.is_empty() {
+                let graph = Rectangle::new(
+                    Vector::new(WIDTH - 210., HEIGHT - 110.),
+                    Vector::new(200., 100.),
+                );
+                gfx.stroke_rect(&graph, Color::GREEN);
+                gfx.stroke_path(
+                    &graph_points(&frame.largest_mass_history, &graph),
+                    Color::YELLOW,
+                );
+            }
+
+            if !frame.separation_history.is_empty() {
+                let graph = Rectangle::new(
+                    Vector::new(WIDTH - 210., HEIGHT - 220.),
+                    Vector::new(200., 100.),
+                );
+                gfx.stroke_rect(&graph, Color::GREEN);
+                gfx.stroke_path(
+                    &graph_points(&frame.separation_history, &graph),
+                    Color::CYAN,
+                );
+            }
+
+            if let Some(scrubber) = frame.scrubber {
+                // the whole horizon, with the part predicted so far filled in
+                // above the help text, which takes up the bottom 60 or so pixels
+                let track = Rectangle::new(Vector::new(10., HEIGHT - 84.), Vector::new(300., 8.));
+                let per_snapshot = track.size.x / scrubber.total.max(1) as f32;
+                gfx.stroke_rect(&track, Color::GREEN);
+                gfx.fill_rect(
+                    &Rectangle::new(
+                        track.pos,
+                        Vector::new(per_snapshot * scrubber.predicted as f32, track.size.y),
+                    ),
+                    Color::GREEN.with_alpha(0.3),
+                );
+                let knob_x = scrubber
+                    .index
+                    .map_or(0., |index| per_snapshot * (index + 1) as f32);
+                gfx.fill_circle(
+                    &Circle::new(Vector::new(track.pos.x + knob_x, track.pos.y + 4.), 6.),
+                    Color::GREEN,
+                );
+                let text = match scrubber.seconds_ahead {
+                    Some(seconds) => format!("inspecting {:.1}s ahead", seconds),
+                    None => "inspecting now".to_string(),
+                };
+                label_font.draw(
+                    &mut gfx,
+                    &text,
+                    Color::GREEN,
+                    Vector::new(10., HEIGHT - 94.),
+                )?;
+            }
+
+            for (row, control) in CONTROLS.iter().enumerate() {
+                label_font.draw(
+                    &mut gfx,
+                    control,
+                    Color::GREEN,
+                    Vector::new(WIDTH - 160., 20. + row as f32 * 16.),
+                )?;
+            }
+
             gfx.present(&window)?;
         }
+        // the update timer catches up with the time slept away, so physics doesn't slow down
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Some(max_fps), Some(frame_start)) = (max_fps, frame_start) {
+            std::thread::sleep(frame_delay(max_fps, frame_start.elapsed()));
+        }
     }
     Ok(())
 }
+
+/// Saves the bodies and closes the trajectory archive once the app is over, whether it returned
+/// after <Escape> or was dropped mid frame because the window was closed, which never gets back
+/// to the end of the loop
+struct Shutdown {
+    save_to: Option<PathBuf>,
+    core: Core,
+}
+
+impl Drop for Shutdown {
+    fn drop(&mut self) {
+        if let Err(error) = self.core.finish_recording() {
+            error!("{}", error);
+        }
+        let path = match &self.save_to {
+            Some(path) => path,
+            None => return,
+        };
+        let core = &self.core;
+        // quitting shouldn't fail just because the state couldn't be kept
+        match core.save(path) {
+            Ok(()) => info!(
+                "saved to {}: ticks={} bodies={} largest_mass={}",
+                path.display(),
+                core.tick_count().0,
+                core.body_count(),
+                core.largest_mass()
+            ),
+            Err(error) => error!("{}", error),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use nalgebra::{Point2, Vector2};
+
+/// how small a cell has to look from a body, size over distance, before its bodies are lumped together
+/// lower is more exact and slower
+const OPENING_ANGLE: f64 = 0.5;
+/// bodies sharing a spot would otherwise be split forever
+const MAX_DEPTH: usize = 32;
+
+/// Barnes-Hut tree, lets distant groups of bodies pull as a single body at their center of mass
+pub(crate) struct QuadTree {
+    /// `(position, mass)` of every body, indexed the way they were passed in
+    bodies: Vec<(Point2<f64>, f64)>,
+    cells: Vec<Cell>,
+}
+
+struct Cell {
+    center: Point2<f64>,
+    half_size: f64,
+    mass: f64,
+    center_of_mass: Point2<f64>,
+    /// bodies in a leaf, empty for branches
+    bodies: Vec<usize>,
+    children: Vec<usize>,
+}
+
+impl QuadTree {
+    pub(crate) fn new(bodies: Vec<(Point2<f64>, f64)>) -> QuadTree {
+        let mut tree = QuadTree {
+            bodies,
+            cells: vec![],
+        };
+        if tree.bodies.is_empty() {
+            return tree;
+        }
+        let (min, max) = tree.bodies.iter().fold(
+            (
+                Point2::new(f64::INFINITY, f64::INFINITY),
+                Point2::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), (position, _)| (min.inf(position), max.sup(position)),
+        );
+        let half_size = ((max - min).max() / 2.).max(f64::EPSILON);
+        let center = nalgebra::center(&min, &max);
+        let everything = (0..tree.bodies.len()).collect();
+        tree.build(everything, center, half_size, 0);
+        tree
+    }
+
+    /// builds the cell holding `bodies` and everything below it, returns its index
+    fn build(
+        &mut self,
+        bodies: Vec<usize>,
+        center: Point2<f64>,
+        half_size: f64,
+        depth: usize,
+    ) -> usize {
+        let mass = bodies
+            .iter()
+            .map(|&index| self.bodies[index].1)
+            .sum::<f64>();
+        let weighted = bodies
+            .iter()
+            .map(|&index| self.bodies[index].0.coords * self.bodies[index].1)
+            .sum::<Vector2<f64>>();
+        let center_of_mass = if mass > 0. {
+            Point2::from(weighted / mass)
+        } else {
+            center
+        };
+        let cell = self.cells.len();
+        self.cells.push(Cell {
+            center,
+            half_size,
+            mass,
+            center_of_mass,
+            bodies: vec![],
+            children: vec![],
+        });
+        if bodies.len() <= 1 || depth == MAX_DEPTH {
+            self.cells[cell].bodies = bodies;
+            return cell;
+        }
+
+        let mut quadrants = vec![vec![]; 4];
+        for index in bodies {
+            let position = self.bodies[index].0;
+            let quadrant =
+                (position.x >= center.x) as usize + 2 * (position.y >= center.y) as usize;
+            quadrants[quadrant].push(index);
+        }
+        let quarter = half_size / 2.;
+        for (quadrant, bodies) in quadrants.into_iter().enumerate() {
+            if bodies.is_empty() {
+                continue;
+            }
+            let x = if quadrant % 2 == 1 { quarter } else { -quarter };
+            let y = if quadrant / 2 == 1 { quarter } else { -quarter };
+            let child = self.build(bodies, center + Vector2::new(x, y), quarter, depth + 1);
+            self.cells[cell].children.push(child);
+        }
+        cell
+    }
+
+    /// sum of `pull(position, mass)` over every other body, with far away groups of bodies
+    /// standing in for their members
+    pub(crate) fn pull(
+        &self,
+        body: usize,
+        pull: impl Fn(&Point2<f64>, f64) -> Vector2<f64>,
+    ) -> Vector2<f64> {
+        let position = self.bodies[body].0;
+        let mut total = Vector2::zeros();
+        let mut stack = if self.cells.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(cell) = stack.pop() {
+            let cell = &self.cells[cell];
+            if cell.children.is_empty() {
+                for &other in cell.bodies.iter().filter(|&&other| other != body) {
+                    let (other_position, other_mass) = self.bodies[other];
+                    total += pull(&other_position, other_mass);
+                }
+                continue;
+            }
+            let distance = (cell.center_of_mass - position).magnitude();
+            if !cell.contains(&position) && 2. * cell.half_size < OPENING_ANGLE * distance {
+                total += pull(&cell.center_of_mass, cell.mass);
+            } else {
+                stack.extend(&cell.children);
+            }
+        }
+        total
+    }
+}
+
+impl Cell {
+    fn contains(&self, position: &Point2<f64>) -> bool {
+        (position.x - self.center.x).abs() <= self.half_size
+            && (position.y - self.center.y).abs() <= self.half_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn newtonian(from: Point2<f64>) -> impl Fn(&Point2<f64>, f64) -> Vector2<f64> {
+        move |other: &Point2<f64>, mass: f64| {
+            let difference = other - from;
+            difference.normalize() * mass / difference.magnitude_squared()
+        }
+    }
+
+    #[test]
+    fn nearby_bodies_pull_exactly() {
+        let bodies = vec![
+            (Point2::new(0., 0.), 1.),
+            (Point2::new(10., 0.), 2.),
+            (Point2::new(0., 10.), 3.),
+        ];
+        let tree = QuadTree::new(bodies.clone());
+        let exact =
+            newtonian(bodies[0].0)(&bodies[1].0, 2.) + newtonian(bodies[0].0)(&bodies[2].0, 3.);
+        assert!((tree.pull(0, newtonian(bodies[0].0)) - exact).magnitude() < 1e-12);
+    }
+
+    #[test]
+    fn distant_cluster_pulls_from_its_center_of_mass() {
+        let mut bodies = vec![(Point2::new(0., 0.), 1.)];
+        for i in 0..4 {
+            for j in 0..4 {
+                bodies.push((Point2::new(1000. + i as f64, 1000. + j as f64), 1.));
+            }
+        }
+        let tree = QuadTree::new(bodies.clone());
+        let pull = tree.pull(0, newtonian(bodies[0].0));
+        let exact = bodies[1..]
+            .iter()
+            .map(|(position, mass)| newtonian(bodies[0].0)(position, *mass))
+            .sum::<Vector2<f64>>();
+        assert!((pull - exact).magnitude() < 1e-4 * exact.magnitude());
+    }
+
+    #[test]
+    fn bodies_on_the_same_spot_dont_recurse_forever() {
+        let bodies = vec![(Point2::new(5., 5.), 1.); 3];
+        let tree = QuadTree::new(bodies);
+        assert_eq!(
+            tree.pull(0, |_, mass| Vector2::new(mass, 0.)),
+            Vector2::new(2., 0.)
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::thread;
+
+// below this many terms a branch is summed in a plain loop
+const LEAF_SIZE: usize = 32;
+
+/// sums `term(0) + .. + term(len - 1)` as a fixed binary tree of partial sums
+/// the tree only depends on `len`, so spreading branches over `threads` gives the exact same bits
+pub(crate) fn pairwise_sum<F>(len: usize, threads: usize, term: F) -> f64
+where
+    F: Fn(usize) -> f64 + Sync,
+{
+    sum_range(0, len, threads.max(1), &term)
+}
+
+fn sum_range<F>(start: usize, end: usize, threads: usize, term: &F) -> f64
+where
+    F: Fn(usize) -> f64 + Sync,
+{
+    if end - start <= LEAF_SIZE {
+        return (start..end).map(term).sum();
+    }
+    let middle = start + (end - start) / 2;
+    if threads > 1 {
+        let right_threads = threads / 2;
+        thread::scope(|scope| {
+            let right = scope.spawn(|| sum_range(middle, end, right_threads, term));
+            let left = sum_range(start, middle, threads - right_threads, term);
+            left + right.join().expect("summing thread panicked")
+        })
+    } else {
+        sum_range(start, middle, 1, term) + sum_range(middle, end, 1, term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_count_does_not_change_the_sum() {
+        // wildly different magnitudes, so any change in summation order shows up
+        let values = (0..10_000)
+            .map(|i| ((i * 7919) % 1013) as f64 * 10f64.powi(i % 17 - 8))
+            .collect::<Vec<_>>();
+        let serial = pairwise_sum(values.len(), 1, |i| values[i]);
+        for threads in 2..9 {
+            let parallel = pairwise_sum(values.len(), threads, |i| values[i]);
+            assert_eq!(serial.to_bits(), parallel.to_bits(), "{} threads", threads);
+        }
+        let naive: f64 = values.iter().sum();
+        assert!((serial - naive).abs() <= naive.abs() * 1e-12);
+    }
+
+    #[test]
+    fn empty_sum_is_zero() {
+        assert_eq!(pairwise_sum(0, 4, |_| 1.), 0.);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// hand made starting layout, read from a json file instead of generating bodies
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Scenario {
+    /// no sun at all when left out
+    #[serde(default)]
+    pub(crate) sun: Option<ScenarioSun>,
+    pub(crate) bodies: Vec<ScenarioBody>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ScenarioSun {
+    pub(crate) position: [f64; 2],
+    pub(crate) mass: f64,
+    /// `[r, g, b]`, yellow when left out
+    #[serde(default)]
+    pub(crate) color: Option<[u8; 3]>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ScenarioBody {
+    #[serde(default)]
+    pub(crate) name: Option<String>,
+    pub(crate) position: [f64; 2],
+    #[serde(default)]
+    pub(crate) velocity: [f64; 2],
+    pub(crate) mass: f64,
+    #[serde(default = "default_density")]
+    pub(crate) density: f64,
+    /// `[r, g, b]`, drawn the usual way when left out
+    #[serde(default)]
+    pub(crate) color: Option<[u8; 3]>,
+    /// bit set of the populations this body belongs to
+    #[serde(default = "default_categories")]
+    pub(crate) categories: u32,
+    /// bit set of the populations this body collides with
+    #[serde(default = "default_collides_with")]
+    pub(crate) collides_with: u32,
+    /// how bouncy the body is in bounce mode, 0 to 1
+    #[serde(default)]
+    pub(crate) restitution: Option<f64>,
+}
+
+fn default_density() -> f64 {
+    1.
+}
+
+fn default_categories() -> u32 {
+    1
+}
+
+fn default_collides_with() -> u32 {
+    u32::MAX
+}
+
+impl Scenario {
+    pub(crate) fn parse(json: &str) -> Result<Scenario, String> {
+        serde_json::from_str(json).map_err(|error| format!("invalid scenario: {}", error))
+    }
+
+    pub(crate) fn load(path: &Path) -> Result<Scenario, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|error| format!("couldn't read {}: {}", path.display(), error))?;
+        Scenario::parse(&json)
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|error| error.to_string())?;
+        fs::write(path, json)
+            .map_err(|error| format!("couldn't write {}: {}", path.display(), error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optional_fields_get_defaults() {
+        let scenario = Scenario::parse(r#"{"bodies": [{"position": [1, 2], "mass": 3}]}"#).unwrap();
+        assert_eq!(scenario.sun, None);
+        assert_eq!(
+            scenario.bodies,
+            vec![ScenarioBody {
+                name: None,
+                position: [1., 2.],
+                velocity: [0., 0.],
+                mass: 3.,
+                density: 1.,
+                color: None,
+                categories: 1,
+                collides_with: u32::MAX,
+                restitution: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_scenarios() {
+        assert!(Scenario::parse(r#"{"bodies": [{"position": [1, 2]}]}"#).is_err());
+        assert!(Scenario::parse(r#"{"bodies": [], "moons": []}"#).is_err());
+        assert!(Scenario::parse("not json").is_err());
+        assert!(Scenario::load(Path::new("/does/not/exist.json")).is_err());
+    }
+}
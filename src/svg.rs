@@ -0,0 +1,89 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use itertools::Itertools;
+use nalgebra::Point2;
+
+// side of the square viewBox the path is scaled into, and the blank border around it
+const VIEW_BOX_SIZE: f64 = 1000.;
+const MARGIN: f64 = 20.;
+
+/// a standalone svg document drawing `points` as one polyline, scaled to fill the viewBox
+pub(crate) fn path_to_svg(points: &[Point2<f64>]) -> String {
+    let min_x = points
+        .iter()
+        .map(|point| point.x)
+        .fold(f64::INFINITY, f64::min);
+    let min_y = points
+        .iter()
+        .map(|point| point.y)
+        .fold(f64::INFINITY, f64::min);
+    let max_x = points
+        .iter()
+        .map(|point| point.x)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let max_y = points
+        .iter()
+        .map(|point| point.y)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let extent = (max_x - min_x).max(max_y - min_y).max(f64::EPSILON);
+    let scale = (VIEW_BOX_SIZE - 2. * MARGIN) / extent;
+
+    let path = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            format!(
+                "{} {:.2} {:.2}",
+                if index == 0 { "M" } else { "L" },
+                MARGIN + (point.x - min_x) * scale,
+                MARGIN + (point.y - min_y) * scale
+            )
+        })
+        .join(" ");
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {size} {size}\">\n\
+         <path d=\"{path}\" fill=\"none\" stroke=\"black\" stroke-width=\"2\"/>\n\
+         </svg>\n",
+        size = VIEW_BOX_SIZE,
+        path = path
+    )
+}
+
+pub(crate) fn write_path_svg(file: &Path, points: &[Point2<f64>]) -> io::Result<()> {
+    fs::write(file, path_to_svg(points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn svg_has_a_point_per_sample_inside_the_view_box() {
+        let points = (0..12)
+            .map(|i| {
+                let angle = i as f64 / 12. * std::f64::consts::PI * 2.;
+                Point2::new(400. + angle.cos() * 50., 300. + angle.sin() * 25.)
+            })
+            .collect::<Vec<_>>();
+
+        let svg = path_to_svg(&points);
+
+        assert!(svg.contains("viewBox=\"0 0 1000 1000\""));
+        let path = svg.split("d=\"").nth(1).unwrap().split('"').next().unwrap();
+        let commands = path
+            .split(' ')
+            .filter(|token| *token == "M" || *token == "L")
+            .count();
+        assert_eq!(commands, 12);
+        let coordinates = path
+            .split(' ')
+            .filter_map(|token| token.parse::<f64>().ok())
+            .collect::<Vec<_>>();
+        assert_eq!(coordinates.len(), 24);
+        assert!(coordinates
+            .iter()
+            .all(|coordinate| (MARGIN..=VIEW_BOX_SIZE - MARGIN).contains(coordinate)));
+    }
+}
@@ -1,6 +1,237 @@
-use nalgebra::Vector2;
-use quicksilver::geom;
+use std::time::Duration;
+
+use itertools::Itertools;
+use nalgebra::{Point2, Vector2};
+use quicksilver::geom::{self, Circle, Rectangle, Vector};
+use quicksilver::graphics::Color;
 
 pub(crate) fn convert(vec: geom::Vector) -> Vector2<f64> {
     Vector2::new(vec.x.into(), vec.y.into())
 }
+
+/// labels fade out when zooming out so a dense field stays readable
+pub(crate) fn label_opacity(zoom_scale: f32) -> f32 {
+    ((zoom_scale - 0.5) / 0.5).clamp(0., 1.)
+}
+
+/// on screen radius of a body, never smaller than `min_pixels` so tiny bodies stay visible zoomed out
+/// only affects drawing, collisions still use the real radius
+pub(crate) fn render_radius(radius: f64, zoom_scale: f32, min_pixels: f32) -> f32 {
+    (radius as f32 * zoom_scale).max(min_pixels)
+}
+
+/// how much effort goes into drawing a body
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Detail {
+    /// a single pixel
+    Point,
+    Circle,
+}
+
+/// bodies with a smaller radius on screen than `point_below` pixels aren't worth a whole circle
+pub(crate) fn level_of_detail(radius: f64, zoom_scale: f32, point_below: f32) -> Detail {
+    if (radius as f32 * zoom_scale) < point_below {
+        Detail::Point
+    } else {
+        Detail::Circle
+    }
+}
+
+/// on screen outline of where a body actually collides, whatever size it's drawn at
+pub(crate) fn collision_outline(position: Point2<f64>, radius: f64, zoom_scale: f32) -> Circle {
+    Circle::new(
+        Vector::new(
+            position.x as f32 * zoom_scale,
+            position.y as f32 * zoom_scale,
+        ),
+        radius as f32 * zoom_scale,
+    )
+}
+
+/// dim grey in the dark, warming up to the sun's color when fully lit
+pub(crate) fn lit_color(illumination: f64) -> Color {
+    let dark = Color::from_rgba(90, 90, 100, 1.);
+    let lit = Color::from_rgba(255, 250, 200, 1.);
+    let light = illumination as f32;
+    Color {
+        r: dark.r + (lit.r - dark.r) * light,
+        g: dark.g + (lit.g - dark.g) * light,
+        b: dark.b + (lit.b - dark.b) * light,
+        a: 1.,
+    }
+}
+
+/// fits `(tick, value)` samples into `area`, with the value axis starting at zero
+pub(crate) fn graph_points(samples: &[(u64, f64)], area: &Rectangle) -> Vec<Vector> {
+    let first_tick = samples.first().map(|(tick, _)| *tick).unwrap_or_default();
+    let last_tick = samples.last().map(|(tick, _)| *tick).unwrap_or_default();
+    let tick_span = (last_tick - first_tick).max(1) as f32;
+    let max_value = samples
+        .iter()
+        .map(|(_, value)| *value)
+        .fold(0., f64::max)
+        .max(f64::EPSILON) as f32;
+    samples
+        .iter()
+        .map(|(tick, value)| {
+            Vector::new(
+                area.pos.x + (tick - first_tick) as f32 / tick_span * area.size.x,
+                area.pos.y + area.size.y - *value as f32 / max_value * area.size.y,
+            )
+        })
+        .collect()
+}
+
+// sharp corners are cut off rather than mitered further out than this many half widths
+const MITER_LIMIT: f64 = 4.;
+
+/// triangles covering a line of some width, to draw paths thicker than a pixel
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ThickLine {
+    /// two per point of the path, on its left and right
+    pub(crate) vertices: Vec<Vector>,
+    /// indices into `vertices`, two per segment
+    pub(crate) triangles: Vec<[u32; 3]>,
+}
+
+/// a `width` pixels wide line through `points`, mitered at the corners
+/// repeated points are dropped first, and with fewer than two left there's nothing to draw
+pub(crate) fn thick_line(points: &[Point2<f64>], width: f32) -> ThickLine {
+    let points = points.iter().copied().dedup().collect::<Vec<_>>();
+    if points.len() < 2 {
+        return ThickLine {
+            vertices: vec![],
+            triangles: vec![],
+        };
+    }
+    let half_width = f64::from(width) / 2.;
+    let normal = |from: &Point2<f64>, to: &Point2<f64>| {
+        let direction = (to - from).normalize();
+        Vector2::new(-direction.y, direction.x)
+    };
+    let vertices = points
+        .iter()
+        .enumerate()
+        .flat_map(|(i, point)| {
+            let before = i
+                .checked_sub(1)
+                .map(|previous| normal(&points[previous], point));
+            let after = points.get(i + 1).map(|next| normal(point, next));
+            let offset = match (before, after) {
+                (Some(before), Some(after)) => {
+                    // turning straight back leaves no corner to miter
+                    let miter = (before + after).try_normalize(1e-9).unwrap_or(before);
+                    miter * half_width / miter.dot(&before).max(1. / MITER_LIMIT)
+                }
+                (Some(normal), None) | (None, Some(normal)) => normal * half_width,
+                (None, None) => unreachable!("there are at least two points"),
+            };
+            vec![point + offset, point - offset]
+        })
+        .map(|vertex| Vector::new(vertex.x as f32, vertex.y as f32))
+        .collect::<Vec<_>>();
+    let triangles = (0..points.len() as u32 - 1)
+        .flat_map(|segment| {
+            let (left, right) = (segment * 2, segment * 2 + 1);
+            vec![[left, right, left + 2], [right, right + 2, left + 2]]
+        })
+        .collect();
+    ThickLine {
+        vertices,
+        triangles,
+    }
+}
+
+/// how long to wait after a frame that took `last_frame` to keep to `max_fps`, nothing once it's already late
+pub(crate) fn frame_delay(max_fps: f32, last_frame: Duration) -> Duration {
+    Duration::from_secs_f64(1. / f64::from(max_fps))
+        .checked_sub(last_frame)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_bodies_are_drawn_at_least_min_pixels() {
+        assert_eq!(render_radius(10., 1., 2.), 10.);
+        assert_eq!(render_radius(10., 0.5, 2.), 5.);
+        assert_eq!(render_radius(1., 0.5, 2.), 2.);
+        assert_eq!(render_radius(1., 0.5, 0.), 0.5);
+    }
+
+    #[test]
+    fn small_bodies_on_screen_are_drawn_as_points() {
+        assert_eq!(level_of_detail(10., 1., 2.), Detail::Circle);
+        assert_eq!(level_of_detail(10., 0.1, 2.), Detail::Point);
+        assert_eq!(level_of_detail(1., 2., 2.), Detail::Circle);
+        assert_eq!(level_of_detail(1., 1.9, 2.), Detail::Point);
+        // turned off
+        assert_eq!(level_of_detail(0.01, 0.01, 0.), Detail::Circle);
+    }
+
+    #[test]
+    fn thick_lines_have_two_vertices_per_point() {
+        let path = (0..5)
+            .map(|i| Point2::new(i as f64 * 10., 0.))
+            .collect::<Vec<_>>();
+        let line = thick_line(&path, 2.);
+        assert_eq!(line.vertices.len(), 10);
+        assert_eq!(line.triangles.len(), 8);
+        assert!(line
+            .triangles
+            .iter()
+            .flatten()
+            .all(|&index| (index as usize) < line.vertices.len()));
+        assert!(line
+            .vertices
+            .iter()
+            .all(|vertex| (vertex.y.abs() - 1.).abs() < 1e-6));
+
+        // the corner reaches out to where the edges meet
+        let corner = thick_line(
+            &[
+                Point2::new(0., 0.),
+                Point2::new(10., 0.),
+                Point2::new(10., 10.),
+            ],
+            2.,
+        );
+        assert_eq!(corner.vertices.len(), 6);
+        let (inner, outer) = (corner.vertices[2], corner.vertices[3]);
+        assert!((inner.x - 9.).abs() < 1e-6 && (inner.y - 1.).abs() < 1e-6);
+        assert!((outer.x - 11.).abs() < 1e-6 && (outer.y + 1.).abs() < 1e-6);
+
+        let repeated = [
+            Point2::new(0., 0.),
+            Point2::new(0., 0.),
+            Point2::new(5., 0.),
+        ];
+        assert_eq!(thick_line(&repeated, 1.).vertices.len(), 4);
+        assert_eq!(thick_line(&repeated[..2], 1.).vertices.len(), 0);
+    }
+
+    #[test]
+    fn collision_outline_follows_the_zoom() {
+        let outline = collision_outline(Point2::new(100., 50.), 4., 0.5);
+        assert_eq!((outline.pos.x, outline.pos.y), (50., 25.));
+        assert_eq!(outline.radius, 2.);
+
+        // unlike the drawn radius, it isn't blown up to stay visible
+        let tiny = collision_outline(Point2::new(0., 0.), 1., 0.25);
+        assert_eq!(tiny.radius, 0.25);
+        assert!(render_radius(1., 0.25, 2.) > tiny.radius);
+    }
+
+    #[test]
+    fn frame_delay_fills_up_the_frame() {
+        assert_eq!(
+            frame_delay(50., Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+        assert_eq!(frame_delay(50., Duration::ZERO), Duration::from_millis(20));
+        assert_eq!(frame_delay(50., Duration::from_millis(20)), Duration::ZERO);
+        assert_eq!(frame_delay(50., Duration::from_millis(35)), Duration::ZERO);
+    }
+}